@@ -1,6 +1,16 @@
 // Valores por defecto
 pub const DEFAULT_PAGE: u32 = 1;
 pub const DEFAULT_LIMIT: u32 = 20;
+/// Segundos que se espera a que una unidad arranque al aplicar un cambio
+pub const DEFAULT_APPLY_TIMEOUT: u64 = 30;
+/// Límite del tiempo de espera que puede pedir un cliente
+pub const MAX_APPLY_TIMEOUT: u64 = 300;
+/// Líneas del journal que se devuelven cuando falla un cambio aplicado
+pub const APPLY_LOG_LINES: usize = 50;
+/// Líneas del journal que se devuelven si no se indica otra cosa
+pub const DEFAULT_LOG_LINES: usize = 100;
+/// Límite de líneas del journal por petición
+pub const MAX_LOG_LINES: usize = 10000;
 
 
 
@@ -0,0 +1,139 @@
+//! Extractores de axum que responden a los errores con el sobre de la API
+//! (`ApiResponse` con `INVALID_REQUEST`) en lugar de texto plano
+
+use axum::{
+    extract::{FromRequest, FromRequestParts, OptionalFromRequest, Request},
+    http::request::Parts,
+};
+use serde::de::DeserializeOwned;
+
+use crate::models::{AppError, ErrorCode};
+
+/// Error de un extractor de axum, con su mensaje
+fn rejection(rejection: impl std::fmt::Display) -> AppError {
+    AppError::new(ErrorCode::InvalidRequest, rejection.to_string())
+}
+
+/// Cuerpo JSON, igual que `axum::Json`
+#[derive(Debug, Default)]
+pub struct Json<T>(pub T);
+
+impl<T, S> FromRequest<S> for Json<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        <axum::Json<T> as FromRequest<S>>::from_request(req, state)
+            .await
+            .map(|axum::Json(value)| Json(value))
+            .map_err(rejection)
+    }
+}
+
+impl<T, S> OptionalFromRequest<S> for Json<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Option<Self>, Self::Rejection> {
+        <axum::Json<T> as OptionalFromRequest<S>>::from_request(req, state)
+            .await
+            .map(|value| value.map(|axum::Json(value)| Json(value)))
+            .map_err(rejection)
+    }
+}
+
+/// Parámetros de consulta, igual que `axum::extract::Query`
+pub struct Query<T>(pub T);
+
+impl<T, S> FromRequestParts<S> for Query<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        axum::extract::Query::from_request_parts(parts, state)
+            .await
+            .map(|axum::extract::Query(value)| Query(value))
+            .map_err(rejection)
+    }
+}
+
+/// Parámetros de la ruta, igual que `axum::extract::Path`
+pub struct Path<T>(pub T);
+
+impl<T, S> FromRequestParts<S> for Path<T>
+where
+    T: DeserializeOwned + Send,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        axum::extract::Path::from_request_parts(parts, state)
+            .await
+            .map(|axum::extract::Path(value)| Path(value))
+            .map_err(rejection)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{Router, body::Body, http::StatusCode, routing::post};
+    use serde::Deserialize;
+    use serde_json::Value;
+    use tower::ServiceExt;
+
+    #[derive(Deserialize)]
+    struct Params {
+        #[allow(dead_code)]
+        page: u32,
+    }
+
+    async fn handler(
+        Path(_): Path<i64>,
+        Query(_): Query<Params>,
+        Json(_): Json<Value>,
+    ) -> &'static str {
+        "ok"
+    }
+
+    async fn send(uri: &str, body: &str) -> (StatusCode, Value) {
+        let app = Router::new().route("/{id}", post(handler));
+        let request = Request::builder()
+            .uri(uri)
+            .method("POST")
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+    }
+
+    #[tokio::test]
+    async fn test_rejections_use_the_envelope() {
+        for (uri, body) in [
+            ("/abc?page=1", "{}"),
+            ("/1?page=abc", "{}"),
+            ("/1?page=1", "{not json"),
+        ] {
+            let (status, json) = send(uri, body).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{uri} {body}");
+            assert_eq!(json["code"], "INVALID_REQUEST", "{uri} {body}");
+            assert!(json["message"].as_str().is_some_and(|m| !m.is_empty()));
+        }
+        assert_eq!(send("/1?page=1", "{}").await.0, StatusCode::OK);
+    }
+}
@@ -1,4 +1,4 @@
-use crate::models::{ApiResponse, AppState };
+use crate::models::{AppState, CustomResponse};
 use axum::{Router, http::StatusCode, response::IntoResponse, routing};
 use std::sync::Arc;
 
@@ -7,5 +7,5 @@ pub fn router() -> Router<Arc<AppState>> {
 }
 
 async fn check_health() -> impl IntoResponse {
-    ApiResponse::new(StatusCode::OK, "Up and running", None)
+    CustomResponse::api(StatusCode::OK, "Up and running", None)
 }
@@ -0,0 +1,1116 @@
+//! Pruebas del flujo completo de la API contra un systemd falso y
+//! directorios de quadlets temporales
+
+use axum::{
+    Router,
+    body::Body,
+    http::{HeaderMap, Request, StatusCode},
+};
+use serde_json::{Value, json};
+use std::{fs, sync::Arc, time::Duration};
+use tempfile::TempDir;
+use tokio_stream::StreamExt;
+use tower::ServiceExt;
+
+use super::{fallback_404, podman_router, quadlets_router};
+use crate::models::{AppState, QuadletDirectory, QuadletSource, UnitState};
+use crate::podman::PodmanStub;
+use crate::systemd::{FakeSystemd, Generator, Systemd, SystemdManager};
+
+struct TestApp {
+    app: Router,
+    systemd: Arc<FakeSystemd>,
+    podman: PodmanStub,
+    /// Directorio `runtime`, escribible y con más prioridad que `user`
+    runtime: TempDir,
+    /// Directorio `user`, escribible
+    user: TempDir,
+    /// Directorio `admin_users`, de solo lectura
+    admin: TempDir,
+    /// Directorio del generador y del socket de Podman de prueba
+    bin: TempDir,
+}
+
+impl TestApp {
+    fn new() -> Self {
+        let runtime = tempfile::tempdir().unwrap();
+        let user = tempfile::tempdir().unwrap();
+        let admin = tempfile::tempdir().unwrap();
+        let bin = tempfile::tempdir().unwrap();
+        let systemd = Arc::new(FakeSystemd::new());
+        let podman = PodmanStub::new();
+        let app = Router::new()
+            .nest("/quadlets", quadlets_router())
+            .nest("/podman", podman_router())
+            .fallback(fallback_404)
+            .with_state(Arc::new(AppState {
+                secret: "test".to_string(),
+                static_dir: "static".to_string(),
+                directories: vec![
+                    QuadletDirectory {
+                        source: QuadletSource::Runtime,
+                        path: runtime.path().to_path_buf(),
+                    },
+                    QuadletDirectory {
+                        source: QuadletSource::User,
+                        path: user.path().to_path_buf(),
+                    },
+                    QuadletDirectory {
+                        source: QuadletSource::AdminUsers,
+                        path: admin.path().to_path_buf(),
+                    },
+                ],
+                systemd: Systemd::new(systemd.clone()),
+                generator: Generator::stub(bin.path()),
+                podman: podman.serve(&bin.path().join("podman.sock")),
+            }));
+        Self {
+            app,
+            systemd,
+            podman,
+            runtime,
+            user,
+            admin,
+            bin,
+        }
+    }
+
+    /// Envía una petición y devuelve el estado, las cabeceras y el cuerpo
+    async fn send(
+        &self,
+        method: &str,
+        uri: &str,
+        body: Option<Value>,
+        headers: &[(&str, &str)],
+    ) -> (StatusCode, HeaderMap, Value) {
+        let mut request = Request::builder().uri(uri).method(method);
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+        let request = match body {
+            Some(body) => request
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string())),
+            None => request.body(Body::empty()),
+        }
+        .unwrap();
+
+        let response = self.app.clone().oneshot(request).await.unwrap();
+        let (status, headers) = (response.status(), response.headers().clone());
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json = serde_json::from_slice(&body).unwrap_or(Value::Null);
+        (status, headers, json)
+    }
+
+    async fn save(&self, name: &str, content: &str) -> (StatusCode, HeaderMap, Value) {
+        self.send(
+            "POST",
+            "/quadlets",
+            Some(json!({"name": name, "content": content, "author": "ana"})),
+            &[],
+        )
+        .await
+    }
+}
+
+const WEB: &str = "[Container]\nImage=docker.io/nginx:1.27\nPublishPort=8080:80\n";
+
+#[tokio::test]
+async fn test_save_writes_file_and_reloads_systemd() {
+    let test = TestApp::new();
+
+    let (status, _, json) = test.save("apps/web.container", WEB).await;
+
+    assert_eq!(status, StatusCode::OK, "{json}");
+    assert_eq!(json["data"]["relative_path"], "apps/web.container");
+    assert_eq!(
+        fs::read_to_string(test.user.path().join("apps/web.container")).unwrap(),
+        WEB
+    );
+    assert_eq!(test.systemd.calls(), ["daemon-reload"]);
+
+    let (status, headers, json) = test
+        .send("GET", "/quadlets/apps%2Fweb.container", None, &[])
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["data"]["content"], WEB);
+    assert_eq!(headers["etag"], json["data"]["etag"].as_str().unwrap());
+
+    let (_, _, json) = test
+        .send("GET", "/quadlets/apps%2Fweb.container/revisions", None, &[])
+        .await;
+    assert_eq!(json["data"][0]["author"], "ana");
+}
+
+#[tokio::test]
+async fn test_bad_requests_use_the_envelope() {
+    let test = TestApp::new();
+    test.save("web.container", WEB).await;
+
+    for uri in ["/quadlets?kind=bogus", "/quadlets?page=abc"] {
+        let (status, _, json) = test.send("GET", uri, None, &[]).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{uri}");
+        assert_eq!(json["code"], "INVALID_REQUEST", "{uri}");
+    }
+
+    let request = Request::builder()
+        .uri("/quadlets/web.container")
+        .method("PUT")
+        .header("content-type", "application/json")
+        .body(Body::from("{\"content\": "))
+        .unwrap();
+    let response = test.app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let json: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["code"], "INVALID_REQUEST");
+    assert_eq!(test.systemd.calls(), ["daemon-reload"]);
+}
+
+#[tokio::test]
+async fn test_save_invalid_content_does_not_reload() {
+    let test = TestApp::new();
+
+    let (status, _, json) = test
+        .save(
+            "web.container",
+            "[Container]\nImage=nginx\nPublishPorts=80:80\n",
+        )
+        .await;
+
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(json["code"], "QUADLET_INVALID_CONTENT");
+    assert!(!test.user.path().join("web.container").exists());
+    assert!(test.systemd.calls().is_empty());
+}
+
+#[tokio::test]
+async fn test_save_reports_reload_failure() {
+    let test = TestApp::new();
+    test.systemd.fail("daemon-reload");
+
+    let (status, _, json) = test.save("web.container", WEB).await;
+
+    assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+    assert_eq!(json["code"], "SYSTEMD_RELOAD_FAILED");
+    // El archivo ya está escrito; solo ha fallado la recarga
+    assert!(test.user.path().join("web.container").is_file());
+}
+
+#[tokio::test]
+async fn test_save_reports_history_failure() {
+    let test = TestApp::new();
+    // El repositorio de revisiones no se puede crear
+    fs::write(test.user.path().join(".quma"), "").unwrap();
+
+    let (status, _, json) = test.save("web.container", WEB).await;
+
+    assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+    assert_eq!(json["code"], "HISTORY_FAILED");
+    // El archivo ya está escrito y systemd lo ha vuelto a leer
+    assert!(test.user.path().join("web.container").is_file());
+    assert_eq!(test.systemd.calls(), ["daemon-reload"]);
+}
+
+#[tokio::test]
+async fn test_replace_with_stale_etag_is_rejected() {
+    let test = TestApp::new();
+    let (_, _, saved) = test.save("web.container", WEB).await;
+    let etag = saved["data"]["etag"].as_str().unwrap().to_string();
+
+    let body = json!({"content": "[Container]\nImage=docker.io/nginx:1.28\n"});
+    let (status, _, json) = test
+        .send(
+            "PUT",
+            "/quadlets/web.container",
+            Some(body.clone()),
+            &[("if-match", "\"stale\"")],
+        )
+        .await;
+    assert_eq!(status, StatusCode::PRECONDITION_FAILED);
+    assert_eq!(json["code"], "QUADLET_MODIFIED");
+    assert_eq!(json["data"]["content"], WEB);
+    assert_eq!(test.systemd.calls(), ["daemon-reload"]);
+
+    let (status, _, json) = test
+        .send(
+            "PUT",
+            "/quadlets/web.container",
+            Some(body),
+            &[("if-match", &etag)],
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{json}");
+    assert_eq!(test.systemd.calls(), ["daemon-reload", "daemon-reload"]);
+}
+
+#[tokio::test]
+async fn test_rollback_goes_through_save() {
+    let test = TestApp::new();
+    test.save("web.container", WEB).await;
+    let (_, _, saved) = test
+        .save("web.container", "[Container]\nImage=docker.io/nginx:1.28\n")
+        .await;
+    let etag = saved["data"]["etag"].as_str().unwrap().to_string();
+    let (_, _, json) = test
+        .send("GET", "/quadlets/web.container/revisions", None, &[])
+        .await;
+    let revision = json["data"][1]["id"].as_str().unwrap().to_string();
+    let uri = format!("/quadlets/web.container/revisions/{}/rollback", revision);
+
+    let (status, _, json) = test
+        .send("POST", &uri, None, &[("if-match", "\"stale\"")])
+        .await;
+    assert_eq!(status, StatusCode::PRECONDITION_FAILED);
+    assert_eq!(json["code"], "QUADLET_MODIFIED");
+
+    let body = json!({"author": "ana", "apply": true});
+    let (status, _, json) = test
+        .send("POST", &uri, Some(body), &[("if-match", &etag)])
+        .await;
+    assert_eq!(status, StatusCode::OK, "{json}");
+    assert_eq!(json["data"]["content"], WEB);
+    assert_eq!(json["data"]["source"], "user");
+    assert_eq!(json["data"]["writable"], true);
+    assert_eq!(
+        test.systemd.calls(),
+        [
+            "daemon-reload",
+            "daemon-reload",
+            "daemon-reload",
+            "restart web.service"
+        ]
+    );
+    let (_, _, json) = test
+        .send("GET", "/quadlets/web.container/revisions", None, &[])
+        .await;
+    assert_eq!(
+        json["data"][0]["message"],
+        format!("Rollback web.container to {}", &revision[..8])
+    );
+}
+
+#[tokio::test]
+async fn test_rename_with_stale_etag_is_rejected() {
+    let test = TestApp::new();
+    let (_, _, saved) = test.save("web.container", WEB).await;
+    let etag = saved["data"]["etag"].as_str().unwrap().to_string();
+    let body = json!({"name": "apps/web.container"});
+
+    let (status, _, json) = test
+        .send(
+            "POST",
+            "/quadlets/web.container/rename",
+            Some(body.clone()),
+            &[("if-match", "\"stale\"")],
+        )
+        .await;
+    assert_eq!(status, StatusCode::PRECONDITION_FAILED);
+    assert_eq!(json["code"], "QUADLET_MODIFIED");
+    assert!(test.user.path().join("web.container").exists());
+
+    let (status, _, json) = test
+        .send(
+            "POST",
+            "/quadlets/web.container/rename",
+            Some(body),
+            &[("if-match", &etag)],
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{json}");
+    assert!(test.user.path().join("apps/web.container").exists());
+}
+
+#[tokio::test]
+async fn test_delete_stops_and_disables_unit() {
+    let test = TestApp::new();
+    test.save("web.container", WEB).await;
+    test.systemd.set_unit(UnitState {
+        unit: "web.service".to_string(),
+        load_state: "loaded".to_string(),
+        active_state: "active".to_string(),
+        sub_state: "running".to_string(),
+        unit_file_state: "generated".to_string(),
+        ..Default::default()
+    });
+
+    let (status, _, json) = test
+        .send(
+            "DELETE",
+            "/quadlets/web.container?stop=true&disable=true",
+            None,
+            &[],
+        )
+        .await;
+
+    assert_eq!(status, StatusCode::OK, "{json}");
+    assert_eq!(json["data"]["unit"]["active_state"], "inactive");
+    assert_eq!(json["data"]["unit"]["unit_file_state"], "disabled");
+    assert!(!test.user.path().join("web.container").exists());
+    assert_eq!(
+        test.systemd.calls(),
+        [
+            "daemon-reload",
+            "stop web.service",
+            "disable web.service",
+            "daemon-reload"
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_delete_with_dependents() {
+    let test = TestApp::new();
+    test.save("app.network", "[Network]\n").await;
+    test.save(
+        "db.container",
+        "[Container]\nImage=docker.io/postgres:16\nNetwork=app.network\n",
+    )
+    .await;
+    test.save(
+        "web.container",
+        "[Unit]\nRequires=db.service\n\n[Container]\nImage=nginx\n",
+    )
+    .await;
+    // Solo ordena su arranque: ni bloquea el borrado ni se borra
+    test.save(
+        "backup.container",
+        "[Unit]\nAfter=db.service\n\n[Container]\nImage=restic\n",
+    )
+    .await;
+    test.systemd.set_unit(UnitState {
+        unit: "db.service".to_string(),
+        load_state: "loaded".to_string(),
+        active_state: "active".to_string(),
+        sub_state: "running".to_string(),
+        ..Default::default()
+    });
+
+    // Por defecto se rechaza y se indica qué depende del Quadlet
+    let (status, _, json) = test
+        .send("DELETE", "/quadlets/app.network", None, &[])
+        .await;
+    assert_eq!(status, StatusCode::CONFLICT, "{json}");
+    assert_eq!(json["code"], "QUADLET_HAS_DEPENDENTS");
+    let dependents: Vec<&str> = json["data"]["dependents"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|d| d["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(dependents, ["web.container", "db.container"]);
+    assert_eq!(json["data"]["deleted"], json!([]));
+    assert!(test.user.path().join("app.network").exists());
+
+    let (status, _, json) = test
+        .send(
+            "DELETE",
+            "/quadlets/app.network?cascade=true&dry_run=true",
+            None,
+            &[],
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{json}");
+    assert_eq!(
+        json["data"]["deleted"],
+        json!(["web.container", "db.container", "app.network"])
+    );
+    assert_eq!(json["data"]["references"][0]["from"], "db.container");
+    assert!(test.user.path().join("web.container").exists());
+
+    let calls = test.systemd.calls().len();
+    let (status, _, json) = test
+        .send("DELETE", "/quadlets/app.network?cascade=true", None, &[])
+        .await;
+    assert_eq!(status, StatusCode::OK, "{json}");
+    for name in ["web.container", "db.container", "app.network"] {
+        assert!(!test.user.path().join(name).exists(), "{name}");
+    }
+    assert!(test.user.path().join("backup.container").exists());
+    assert_eq!(
+        test.systemd.calls()[calls..],
+        ["stop db.service", "daemon-reload"]
+    );
+}
+
+#[tokio::test]
+async fn test_failed_cascade_reloads_systemd() {
+    let test = TestApp::new();
+    test.save("app.network", "[Network]\n").await;
+    test.save(
+        "db.container",
+        "[Container]\nImage=docker.io/postgres:16\nNetwork=app.network\n",
+    )
+    .await;
+    test.save(
+        "web.container",
+        "[Unit]\nBindsTo=db.service\n\n[Container]\nImage=nginx\n",
+    )
+    .await;
+    test.systemd.set_unit(UnitState {
+        unit: "db.service".to_string(),
+        load_state: "loaded".to_string(),
+        active_state: "active".to_string(),
+        sub_state: "running".to_string(),
+        ..Default::default()
+    });
+    test.systemd.fail("stop db.service");
+
+    let calls = test.systemd.calls().len();
+    let (status, _, json) = test
+        .send("DELETE", "/quadlets/app.network?cascade=true", None, &[])
+        .await;
+    assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR, "{json}");
+    assert_eq!(json["code"], "SYSTEMD_COMMAND_FAILED");
+    // `web` ya se ha borrado, así que systemd se recarga igualmente
+    assert!(!test.user.path().join("web.container").exists());
+    assert!(test.user.path().join("db.container").exists());
+    assert!(test.user.path().join("app.network").exists());
+    assert_eq!(
+        test.systemd.calls()[calls..],
+        ["stop db.service", "daemon-reload"]
+    );
+}
+
+#[tokio::test]
+async fn test_force_delete_leaves_dangling_references() {
+    let test = TestApp::new();
+    test.save("app.network", "[Network]\n").await;
+    test.save(
+        "web.container",
+        "[Container]\nImage=nginx\nNetwork=app.network\n",
+    )
+    .await;
+
+    let (status, _, json) = test
+        .send("DELETE", "/quadlets/app.network?force=true", None, &[])
+        .await;
+    assert_eq!(status, StatusCode::OK, "{json}");
+    assert_eq!(json["data"]["deleted"], json!(["app.network"]));
+    assert!(test.user.path().join("web.container").exists());
+
+    let (_, _, json) = test.send("GET", "/quadlets/graph", None, &[]).await;
+    assert_eq!(json["data"]["dangling"][0]["to"], "app.network");
+}
+
+#[tokio::test]
+async fn test_read_only_source_is_listed_but_not_deleted() {
+    let test = TestApp::new();
+    fs::write(test.admin.path().join("shared.container"), WEB).unwrap();
+
+    let (status, _, json) = test.send("GET", "/quadlets", None, &[]).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["data"][0]["source"], "admin_users");
+    assert_eq!(json["data"][0]["writable"], false);
+
+    let (status, _, json) = test
+        .send("DELETE", "/quadlets/shared.container", None, &[])
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(json["code"], "QUADLET_READ_ONLY");
+    assert!(test.systemd.calls().is_empty());
+}
+
+#[tokio::test]
+async fn test_same_file_name_in_two_sources() {
+    let test = TestApp::new();
+    fs::write(test.runtime.path().join("web.container"), WEB).unwrap();
+    let (status, _, json) = test.save("apps/web.container", WEB).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["data"]["shadowed"], true);
+
+    let (_, _, json) = test.send("GET", "/quadlets", None, &[]).await;
+    let listed: Vec<(&str, bool)> = json["data"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|q| {
+            (
+                q["relative_path"].as_str().unwrap(),
+                q["shadowed"].as_bool().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        listed,
+        vec![("apps/web.container", true), ("web.container", false)]
+    );
+
+    // Leer cada copia por su ruta da el mismo resultado que el listado
+    for (path, shadowed) in listed {
+        let (status, _, json) = test
+            .send(
+                "GET",
+                &format!("/quadlets/{}", path.replace('/', "%2F")),
+                None,
+                &[],
+            )
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["data"]["shadowed"], shadowed, "{path}");
+    }
+}
+
+#[tokio::test]
+async fn test_dropin_and_rename_reload_systemd() {
+    let test = TestApp::new();
+    test.save("web.container", WEB).await;
+
+    let (status, _, _) = test
+        .send(
+            "PUT",
+            "/quadlets/web.container/dropins/10-env.conf",
+            Some(json!({"content": "[Container]\nEnvironment=A=1\n"})),
+            &[],
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, _, json) = test
+        .send(
+            "POST",
+            "/quadlets/web.container/rename",
+            Some(json!({"name": "apps/site.container"})),
+            &[],
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{json}");
+    assert!(
+        test.user
+            .path()
+            .join("apps/site.container.d/10-env.conf")
+            .is_file()
+    );
+    assert_eq!(test.systemd.calls(), ["daemon-reload"; 3]);
+
+    let (status, _, json) = test.send("GET", "/quadlets/missing", None, &[]).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(json["code"], "QUADLET_INVALID_EXTENSION");
+    let (status, _, json) = test.send("GET", "/unknown", None, &[]).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(json["code"], "NOT_FOUND");
+}
+
+#[tokio::test]
+async fn test_dropins_follow_their_quadlet() {
+    let test = TestApp::new();
+    fs::write(test.runtime.path().join("web.container"), WEB).unwrap();
+    fs::write(test.admin.path().join("db.container"), WEB).unwrap();
+    let body = json!({"content": "[Container]\nEnvironment=A=1\n"});
+
+    // El drop-in va junto al Quadlet, en `runtime`
+    let (status, _, json) = test
+        .send(
+            "PUT",
+            "/quadlets/web.container/dropins/10-env.conf",
+            Some(body.clone()),
+            &[],
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{json}");
+    assert!(
+        test.runtime
+            .path()
+            .join("web.container.d/10-env.conf")
+            .is_file()
+    );
+    assert!(!test.user.path().join("web.container.d").exists());
+
+    // Los de un Quadlet de solo lectura no se pueden crear
+    let (status, _, json) = test
+        .send(
+            "PUT",
+            "/quadlets/db.container/dropins/10-env.conf",
+            Some(body),
+            &[],
+        )
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN, "{json}");
+
+    let (status, _, json) = test
+        .send("DELETE", "/quadlets/web.txt/dropins/10-env.conf", None, &[])
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{json}");
+    let (status, _, json) = test
+        .send(
+            "DELETE",
+            "/quadlets/web.container/dropins/10-env.txt",
+            None,
+            &[],
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{json}");
+
+    let (status, _, json) = test
+        .send(
+            "DELETE",
+            "/quadlets/web.container/dropins/10-env.conf",
+            None,
+            &[],
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{json}");
+    assert!(!test.runtime.path().join("web.container.d").exists());
+}
+
+#[tokio::test]
+async fn test_unit_lifecycle() {
+    let test = TestApp::new();
+    test.save("web.container", WEB).await;
+
+    // Si el generador no ha creado la unidad no hay nada que arrancar
+    let (status, _, json) = test
+        .send("POST", "/quadlets/web.container/start", None, &[])
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(json["code"], "UNIT_NOT_FOUND");
+
+    test.systemd.set_unit(UnitState {
+        unit: "web.service".to_string(),
+        load_state: "loaded".to_string(),
+        active_state: "inactive".to_string(),
+        sub_state: "dead".to_string(),
+        unit_file_state: "generated".to_string(),
+        ..Default::default()
+    });
+    for (action, active_state, sub_state) in [
+        ("start", "active", "running"),
+        ("restart", "active", "running"),
+        ("stop", "inactive", "dead"),
+    ] {
+        let uri = format!("/quadlets/web.container/{}", action);
+        let (status, _, json) = test.send("POST", &uri, None, &[]).await;
+        assert_eq!(status, StatusCode::OK, "{action}: {json}");
+        assert_eq!(json["data"]["unit"], "web.service");
+        assert_eq!(json["data"]["active_state"], active_state);
+        assert_eq!(json["data"]["sub_state"], sub_state);
+    }
+    assert_eq!(
+        test.systemd.calls(),
+        [
+            "daemon-reload",
+            "start web.service",
+            "restart web.service",
+            "stop web.service"
+        ]
+    );
+
+    test.systemd.fail("reload web.service");
+    let (status, _, json) = test
+        .send("POST", "/quadlets/web.container/reload", None, &[])
+        .await;
+    assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+    assert_eq!(json["code"], "SYSTEMD_COMMAND_FAILED");
+}
+
+#[tokio::test]
+async fn test_unit_lifecycle_honours_service_name() {
+    let test = TestApp::new();
+    test.save("data.volume", "[Volume]\n").await;
+    test.save("db.pod", "[Pod]\nServiceName=database\n").await;
+    for unit in ["data-volume.service", "database.service"] {
+        test.systemd.set_unit(UnitState {
+            unit: unit.to_string(),
+            load_state: "loaded".to_string(),
+            ..Default::default()
+        });
+    }
+
+    let (status, _, json) = test
+        .send("POST", "/quadlets/data.volume/start", None, &[])
+        .await;
+    assert_eq!(status, StatusCode::OK, "{json}");
+    assert_eq!(json["data"]["unit"], "data-volume.service");
+    let (status, _, json) = test
+        .send("POST", "/quadlets/db.pod/restart", None, &[])
+        .await;
+    assert_eq!(status, StatusCode::OK, "{json}");
+    assert_eq!(json["data"]["unit"], "database.service");
+    assert_eq!(json["message"], "Unit database.service restarted");
+}
+
+#[tokio::test]
+async fn test_save_and_apply_restarts_unit() {
+    let test = TestApp::new();
+
+    let body = json!({"name": "web.container", "content": WEB, "apply": true});
+    let (status, _, json) = test.send("POST", "/quadlets", Some(body), &[]).await;
+
+    assert_eq!(status, StatusCode::OK, "{json}");
+    assert_eq!(json["message"], "Quadlet saved and applied");
+    assert_eq!(
+        test.systemd.calls(),
+        ["daemon-reload", "restart web.service"]
+    );
+    assert!(test.systemd.status("web.service").unwrap().is_active());
+}
+
+#[tokio::test]
+async fn test_save_and_apply_restores_previous_version() {
+    let test = TestApp::new();
+    test.save("web.container", WEB).await;
+    test.systemd.start("web.service").unwrap();
+    test.systemd.crash("web.service");
+    test.systemd
+        .push_log("web.service", "Error: image not known");
+
+    let body = json!({
+        "content": "[Container]\nImage=docker.io/nginx:broken\n",
+        "apply": true,
+        "apply_timeout": 5,
+    });
+    let (status, _, json) = test
+        .send("PUT", "/quadlets/web.container", Some(body), &[])
+        .await;
+
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(json["code"], "QUADLET_APPLY_FAILED");
+    assert_eq!(json["data"]["unit"]["active_state"], "failed");
+    assert_eq!(json["data"]["logs"], json!(["Error: image not known"]));
+    assert_eq!(
+        fs::read_to_string(test.user.path().join("web.container")).unwrap(),
+        WEB
+    );
+    // Se restaura, se recarga y se vuelve a arrancar la versión anterior
+    assert_eq!(
+        test.systemd.calls(),
+        [
+            "daemon-reload",
+            "start web.service",
+            "daemon-reload",
+            "restart web.service",
+            "daemon-reload",
+            "restart web.service"
+        ]
+    );
+    let (_, _, json) = test
+        .send("GET", "/quadlets/web.container/revisions", None, &[])
+        .await;
+    assert_eq!(
+        json["data"][0]["message"],
+        "Restore web.container after failed apply"
+    );
+}
+
+#[tokio::test]
+async fn test_save_and_apply_removes_new_file_on_failure() {
+    let test = TestApp::new();
+    test.systemd.fail("restart web.service");
+
+    let body = json!({"name": "web.container", "content": WEB, "apply": true});
+    let (status, _, json) = test.send("POST", "/quadlets", Some(body), &[]).await;
+
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(json["code"], "QUADLET_APPLY_FAILED");
+    assert!(!test.user.path().join("web.container").exists());
+    assert_eq!(
+        test.systemd.calls(),
+        ["daemon-reload", "restart web.service", "daemon-reload"]
+    );
+}
+
+#[tokio::test]
+async fn test_list_includes_unit_status() {
+    let test = TestApp::new();
+    test.save("web.container", WEB).await;
+    test.save("data.volume", "[Volume]\n").await;
+    fs::write(test.admin.path().join("web.container"), WEB).unwrap();
+    test.systemd.set_unit(UnitState {
+        unit: "web.service".to_string(),
+        load_state: "loaded".to_string(),
+        active_state: "active".to_string(),
+        sub_state: "running".to_string(),
+        since: "Sat 2026-10-17 09:12:03 CEST".to_string(),
+        main_pid: 4242,
+        restart_count: 1,
+        ..Default::default()
+    });
+
+    let (status, _, json) = test.send("GET", "/quadlets", None, &[]).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(json["data"][0].get("unit").is_none());
+
+    let (status, _, json) = test
+        .send("GET", "/quadlets?include_status=true&sort=name", None, &[])
+        .await;
+    assert_eq!(status, StatusCode::OK, "{json}");
+    let data = json["data"].as_array().unwrap();
+    assert_eq!(data.len(), 3);
+    // El generador no ha creado la unidad del volumen
+    assert_eq!(data[0]["relative_path"], "data.volume");
+    assert_eq!(data[0]["unit"]["unit"], "data-volume.service");
+    assert_eq!(data[0]["unit"]["generated"], false);
+    let web = data
+        .iter()
+        .find(|q| q["name"] == "web" && q["source"] == "user")
+        .unwrap();
+    assert_eq!(web["unit"]["active_state"], "active");
+    assert_eq!(web["unit"]["sub_state"], "running");
+    assert_eq!(web["unit"]["main_pid"], 4242);
+    assert_eq!(web["unit"]["restart_count"], 1);
+    assert_eq!(web["unit"]["generated"], true);
+    // El de `admin_users` queda oculto por el del usuario
+    let shadowed = data.iter().find(|q| q["source"] == "admin_users").unwrap();
+    assert_eq!(shadowed["shadowed"], true);
+    assert!(shadowed.get("unit").is_none());
+}
+
+#[tokio::test]
+async fn test_logs() {
+    let test = TestApp::new();
+    test.save("web.container", WEB).await;
+    for line in ["starting", "listening on :80", "GET /"] {
+        test.systemd.push_log("web.service", line);
+    }
+
+    let (status, _, json) = test
+        .send("GET", "/quadlets/web.container/logs?lines=2", None, &[])
+        .await;
+    assert_eq!(status, StatusCode::OK, "{json}");
+    assert_eq!(json["data"]["unit"], "web.service");
+    assert_eq!(json["data"]["lines"], json!(["listening on :80", "GET /"]));
+
+    let (status, _, json) = test
+        .send(
+            "GET",
+            "/quadlets/web.container/logs?priority=loud",
+            None,
+            &[],
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(json["code"], "INVALID_REQUEST");
+    let (status, _, json) = test
+        .send("GET", "/quadlets/db.container/logs", None, &[])
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(json["code"], "QUADLET_NOT_FOUND");
+}
+
+#[tokio::test]
+async fn test_stream_logs() {
+    let test = TestApp::new();
+    test.save("web.container", WEB).await;
+    test.systemd.push_log("web.service", "starting");
+
+    let request = Request::builder()
+        .uri("/quadlets/web.container/logs/stream")
+        .body(Body::empty())
+        .unwrap();
+    let response = test.app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["content-type"], "text/event-stream");
+    let mut body = response.into_body().into_data_stream();
+
+    // Lee eventos hasta recibir la línea esperada
+    let mut received = String::new();
+    let mut next_line = async |expected: &str| {
+        while !received.contains(expected) {
+            let chunk = tokio::time::timeout(Duration::from_secs(5), body.next())
+                .await
+                .expect("timed out waiting for log line")
+                .unwrap()
+                .unwrap();
+            received.push_str(&String::from_utf8_lossy(&chunk));
+        }
+    };
+    next_line("data: starting\n\n").await;
+    test.systemd.push_log("web.service", "listening on :80");
+    next_line("data: listening on :80\n\n").await;
+}
+
+#[tokio::test]
+async fn test_preview_generated_unit() {
+    let test = TestApp::new();
+    test.save("apps/web.container", WEB).await;
+    test.save("db.container", "[Container]\nImage=postgres:16\n")
+        .await;
+
+    // El contenido propuesto sustituye al guardado sin escribirlo
+    let body = json!({
+        "name": "apps/web.container",
+        "content": "[Container]\nImage=docker.io/nginx:1.28\n",
+    });
+    let (status, _, json) = test
+        .send("POST", "/quadlets/preview", Some(body), &[])
+        .await;
+    assert_eq!(status, StatusCode::OK, "{json}");
+    assert_eq!(json["data"]["unit"], "web.service");
+    assert_eq!(
+        json["data"]["content"],
+        "[Service]\nExecStart=/usr/bin/podman run --name systemd-web docker.io/nginx:1.28\n\n"
+    );
+    assert_eq!(json["data"]["messages"], json!([]));
+    assert_eq!(json["data"]["engine"], "podman");
+    assert_eq!(
+        fs::read_to_string(test.user.path().join("apps/web.container")).unwrap(),
+        WEB
+    );
+    assert_eq!(test.systemd.calls(), ["daemon-reload"; 2]);
+
+    let body = json!({"name": "web.container", "content": "[Container]\nPull=always\n"});
+    let (status, _, json) = test
+        .send("POST", "/quadlets/preview", Some(body), &[])
+        .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(json["code"], "QUADLET_NOT_GENERATED");
+    assert_eq!(json["data"]["unit"], "web.service");
+    assert!(
+        json["data"]["messages"][0]
+            .as_str()
+            .unwrap()
+            .contains("no Image or Rootfs key")
+    );
+}
+
+#[tokio::test]
+async fn test_preview_with_native_translator() {
+    let test = TestApp::new();
+    test.save("data.volume", "[Volume]\n").await;
+    let body = json!({
+        "name": "web.container",
+        "content": "[Container]\nImage=docker.io/nginx:1.27\nVolume=data.volume:/data\n",
+    });
+
+    let (status, _, json) = test
+        .send(
+            "POST",
+            "/quadlets/preview?native=true",
+            Some(body.clone()),
+            &[],
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{json}");
+    assert_eq!(json["data"]["engine"], "native");
+    assert_eq!(json["data"]["unit"], "web.service");
+    assert!(
+        json["data"]["content"]
+            .as_str()
+            .unwrap()
+            .contains("Requires=data-volume.service")
+    );
+    assert_eq!(json["data"]["command"][1], "run");
+
+    // Sin generador se recurre al traductor propio
+    fs::remove_file(test.bin.path().join("quadlet")).unwrap();
+    let (status, _, json) = test
+        .send("POST", "/quadlets/preview", Some(body), &[])
+        .await;
+    assert_eq!(status, StatusCode::OK, "{json}");
+    assert_eq!(json["data"]["engine"], "native");
+
+    let body = json!({"name": "web.container", "content": "[Container]\n"});
+    let (status, _, json) = test
+        .send("POST", "/quadlets/preview", Some(body), &[])
+        .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(json["code"], "QUADLET_NOT_GENERATED");
+    assert_eq!(json["data"]["messages"], json!(["No Image key specified"]));
+}
+
+#[tokio::test]
+async fn test_dependency_graph() {
+    let test = TestApp::new();
+    test.save("app.network", "[Network]\n").await;
+    test.save(
+        "db.container",
+        "[Container]\nImage=docker.io/postgres:16\nNetwork=app.network\n",
+    )
+    .await;
+    test.save(
+        "web.container",
+        "[Unit]\nRequires=db.service\n\n[Container]\nImage=docker.io/nginx:1.27\n\
+         Network=app.network\nVolume=cache.volume:/cache\n",
+    )
+    .await;
+    // El del administrador queda oculto y no aporta referencias
+    fs::write(
+        test.admin.path().join("app.network"),
+        "[Unit]\nAfter=web.container\n\n[Network]\n",
+    )
+    .unwrap();
+
+    let (status, _, json) = test.send("GET", "/quadlets/graph", None, &[]).await;
+    assert_eq!(status, StatusCode::OK, "{json}");
+    assert_eq!(json["data"]["nodes"].as_array().unwrap().len(), 3);
+    assert_eq!(json["data"]["edges"].as_array().unwrap().len(), 4);
+    assert_eq!(
+        json["data"]["dangling"],
+        json!([{
+            "from": "web.container",
+            "to": "cache.volume",
+            "kind": "volume",
+            "dangling": true,
+        }])
+    );
+    assert_eq!(json["data"]["cycles"], json!([]));
+
+    let (status, _, json) = test
+        .send("GET", "/quadlets/app.network/dependents", None, &[])
+        .await;
+    assert_eq!(status, StatusCode::OK, "{json}");
+    assert_eq!(json["data"]["references"].as_array().unwrap().len(), 2);
+    assert_eq!(
+        json["data"]["dependents"],
+        json!(["db.container", "web.container"])
+    );
+
+    let (_, _, json) = test
+        .send("GET", "/quadlets/db.container/dependents", None, &[])
+        .await;
+    assert_eq!(json["data"]["references"][0]["kind"], "requires");
+    assert_eq!(json["data"]["dependents"], json!(["web.container"]));
+
+    let (status, _, _) = test
+        .send("GET", "/quadlets/missing.container/dependents", None, &[])
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_podman_objects_link_to_quadlets() {
+    let test = TestApp::new();
+    test.save("web.container", WEB).await;
+    test.save("app.network", "[Network]\n").await;
+    test.podman.set(
+        "/containers/json",
+        json!([
+            {
+                "Id": "1f2e",
+                "Names": ["systemd-web"],
+                "State": "running",
+                "Labels": {"PODMAN_SYSTEMD_UNIT": "web.service"},
+            },
+            {"Id": "9a8b", "Names": ["manual"], "State": "exited", "Labels": null},
+        ]),
+    );
+    test.podman.set(
+        "/networks/json",
+        json!([{"name": "systemd-app", "labels": {"PODMAN_SYSTEMD_UNIT": "app-network.service"}}]),
+    );
+
+    let (status, _, json) = test.send("GET", "/podman/containers", None, &[]).await;
+    assert_eq!(status, StatusCode::OK, "{json}");
+    assert_eq!(json["message"], "2 containers");
+    assert_eq!(json["data"][0]["State"], "running");
+    assert_eq!(json["data"][0]["quadlet"]["name"], "web.container");
+    assert_eq!(json["data"][0]["quadlet"]["unit"], "web.service");
+    assert_eq!(json["data"][1]["quadlet"], Value::Null);
+
+    let (status, _, json) = test.send("GET", "/podman/networks", None, &[]).await;
+    assert_eq!(status, StatusCode::OK, "{json}");
+    assert_eq!(json["data"][0]["quadlet"]["name"], "app.network");
+
+    let (status, _, json) = test.send("GET", "/podman/pods", None, &[]).await;
+    assert_eq!(status, StatusCode::BAD_GATEWAY);
+    assert_eq!(json["code"], "PODMAN_REQUEST_FAILED");
+
+    fs::remove_file(test.bin.path().join("podman.sock")).unwrap();
+    let (status, _, json) = test.send("GET", "/podman/images", None, &[]).await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(json["code"], "PODMAN_UNAVAILABLE");
+}
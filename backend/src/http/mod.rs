@@ -1,17 +1,17 @@
-use axum::http::StatusCode;
-use crate::models::ApiResponse;
+use crate::models::{AppError, ErrorCode};
+mod extract;
 mod health;
+#[cfg(test)]
+mod integration_tests;
+mod podman;
 mod quadlets;
 mod users;
 
 pub use health::router as health_router;
+pub use podman::router as podman_router;
 pub use quadlets::router as quadlets_router;
 pub use users::router as users_router;
 
 pub async fn fallback_404() -> impl axum::response::IntoResponse {
-    ApiResponse::new(
-        StatusCode::NOT_FOUND,
-        "Not found",
-        None,
-    )
+    AppError::new(ErrorCode::NotFound, "Not found")
 }
@@ -0,0 +1,55 @@
+use axum::{Router, extract::State, http::StatusCode, routing::get};
+use serde_json::json;
+use std::sync::Arc;
+
+use super::quadlets::scan_all_quadlets;
+use crate::models::{ApiResponse, AppError, AppState};
+use crate::podman::{Resource, link_quadlets};
+
+/// Crea el router para consultar lo que Podman tiene en marcha
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/containers", get(list_containers))
+        .route("/images", get(list_images))
+        .route("/volumes", get(list_volumes))
+        .route("/networks", get(list_networks))
+        .route("/pods", get(list_pods))
+}
+
+/// GET /api/podman/containers - Contenedores, incluidos los parados
+async fn list_containers(State(state): State<Arc<AppState>>) -> Result<ApiResponse, AppError> {
+    list(&state, Resource::Containers).await
+}
+
+/// GET /api/podman/images - Imágenes
+async fn list_images(State(state): State<Arc<AppState>>) -> Result<ApiResponse, AppError> {
+    list(&state, Resource::Images).await
+}
+
+/// GET /api/podman/volumes - Volúmenes
+async fn list_volumes(State(state): State<Arc<AppState>>) -> Result<ApiResponse, AppError> {
+    list(&state, Resource::Volumes).await
+}
+
+/// GET /api/podman/networks - Redes
+async fn list_networks(State(state): State<Arc<AppState>>) -> Result<ApiResponse, AppError> {
+    list(&state, Resource::Networks).await
+}
+
+/// GET /api/podman/pods - Pods
+async fn list_pods(State(state): State<Arc<AppState>>) -> Result<ApiResponse, AppError> {
+    list(&state, Resource::Pods).await
+}
+
+/// Objetos de Podman tal y como los devuelve su API, con el Quadlet que los
+/// ha creado en `quadlet`
+async fn list(state: &AppState, resource: Resource) -> Result<ApiResponse, AppError> {
+    let mut objects = state.podman.list(resource).await?;
+    let quadlets = scan_all_quadlets(&state.directories);
+    link_quadlets(&mut objects, &quadlets);
+    Ok(ApiResponse::new(
+        StatusCode::OK,
+        &format!("{} {}", objects.len(), resource.as_str()),
+        Some(json!(objects)),
+    ))
+}
@@ -1,13 +1,13 @@
 use axum::{
     Router,
-    extract::{Json, Path},
     http::StatusCode,
-    response::IntoResponse,
     routing::{delete, get, post},
 };
 use std::sync::Arc;
 use serde::{Deserialize, Serialize};
-use crate::models::AppState;
+use serde_json::json;
+use super::extract::{Json, Path};
+use crate::models::{AppError, AppState, CustomResponse, ErrorCode};
 
 /// Usuario del sistema
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -16,6 +16,7 @@ pub struct User {
     pub username: String,
     pub email: String,
     #[serde(skip_serializing)]
+    #[allow(dead_code)] // TODO: Se comprobará en el login
     pub password_hash: String,
 }
 
@@ -36,6 +37,7 @@ pub struct LoginRequest {
 
 /// Response de login exitoso
 #[derive(Debug, Serialize)]
+#[allow(dead_code)] // TODO: Se devolverá cuando haya autenticación
 pub struct LoginResponse {
     pub token: String,
     pub user: UserResponse,
@@ -49,10 +51,14 @@ pub struct UserResponse {
     pub email: String,
 }
 
-/// Response de error
-#[derive(Debug, Serialize)]
-pub struct ErrorResponse {
-    pub error: String,
+impl From<User> for UserResponse {
+    fn from(user: User) -> Self {
+        Self {
+            id: user.id,
+            username: user.username,
+            email: user.email,
+        }
+    }
 }
 
 /// Crea el router para gestión de usuarios
@@ -66,88 +72,61 @@ pub fn router() -> Router<Arc<AppState>> {
 }
 
 /// GET /api/users - Lista todos los usuarios
-async fn list_users() -> Result<Json<Vec<UserResponse>>, (StatusCode, Json<ErrorResponse>)> {
+async fn list_users() -> Result<CustomResponse, AppError> {
     // TODO: Implementar con SQLite
-    Ok(Json(vec![]))
+    let users: Vec<UserResponse> = vec![];
+    Ok(CustomResponse::api(StatusCode::OK, "0 users", Some(json!(users))))
 }
 
 /// GET /api/users/:id - Obtiene un usuario por ID
-async fn get_user(
-    Path(id): Path<i64>,
-) -> Result<Json<UserResponse>, (StatusCode, Json<ErrorResponse>)> {
+async fn get_user(Path(id): Path<i64>) -> Result<CustomResponse, AppError> {
     // TODO: Implementar con SQLite
-    Err((
-        StatusCode::NOT_FOUND,
-        Json(ErrorResponse {
-            error: format!("User {} not found", id),
-        }),
-    ))
+    Err(AppError::new(ErrorCode::UserNotFound, format!("User {} not found", id)))
 }
 
 /// POST /api/users - Crea un nuevo usuario
-async fn create_user(
-    Json(payload): Json<CreateUserRequest>,
-) -> Result<(StatusCode, Json<UserResponse>), (StatusCode, Json<ErrorResponse>)> {
+async fn create_user(Json(payload): Json<CreateUserRequest>) -> Result<CustomResponse, AppError> {
     // Validaciones básicas
     if payload.username.is_empty() {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: "Username cannot be empty".to_string(),
-            }),
-        ));
+        return Err(AppError::new(ErrorCode::UserInvalid, "Username cannot be empty"));
     }
 
     if payload.email.is_empty() {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: "Email cannot be empty".to_string(),
-            }),
-        ));
+        return Err(AppError::new(ErrorCode::UserInvalid, "Email cannot be empty"));
     }
 
     if payload.password.len() < 8 {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: "Password must be at least 8 characters".to_string(),
-            }),
+        return Err(AppError::new(
+            ErrorCode::UserInvalid,
+            "Password must be at least 8 characters",
         ));
     }
 
     // TODO: Implementar creación real con SQLite y hash de password
-    let user_response = UserResponse {
+    let user = User {
         id: 1,
         username: payload.username,
         email: payload.email,
+        password_hash: String::new(),
     };
 
-    Ok((StatusCode::CREATED, Json(user_response)))
+    Ok(CustomResponse::api(StatusCode::CREATED, "User created", Some(json!(UserResponse::from(user)))))
 }
 
 /// DELETE /api/users/:id - Elimina un usuario
-async fn delete_user(Path(id): Path<i64>) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
+async fn delete_user(Path(id): Path<i64>) -> Result<CustomResponse, AppError> {
     // TODO: Implementar con SQLite
-    Err((
-        StatusCode::NOT_FOUND,
-        Json(ErrorResponse {
-            error: format!("User {} not found", id),
-        }),
-    ))
+    Err(AppError::new(ErrorCode::UserNotFound, format!("User {} not found", id)))
 }
 
 /// POST /api/users/login - Login de usuario
-async fn login(
-    Json(payload): Json<LoginRequest>,
-) -> Result<Json<LoginResponse>, (StatusCode, Json<ErrorResponse>)> {
+async fn login(Json(payload): Json<LoginRequest>) -> Result<CustomResponse, AppError> {
+    if payload.username.is_empty() || payload.password.is_empty() {
+        return Err(AppError::new(ErrorCode::InvalidCredentials, "Username and password are required"));
+    }
+
     // TODO: Implementar autenticación real con SQLite y verificación de password
-    Err((
-        StatusCode::UNAUTHORIZED,
-        Json(ErrorResponse {
-            error: "Invalid credentials".to_string(),
-        }),
-    ))
+    Err(AppError::new(ErrorCode::InvalidCredentials, "Invalid credentials"))
 }
 
 #[cfg(test)]
@@ -157,8 +136,22 @@ mod tests {
         body::Body,
         http::{Request, StatusCode},
     };
+    use crate::models::ApiResponse;
+    use crate::podman::Podman;
+    use crate::systemd::{FakeSystemd, Generator, Systemd};
     use tower::ServiceExt;
 
+    fn test_app() -> Router {
+        router().with_state(Arc::new(AppState {
+            secret: "test".to_string(),
+            static_dir: "static".to_string(),
+            directories: vec![],
+            systemd: Systemd::new(Arc::new(FakeSystemd::new())),
+            generator: Generator::default(),
+            podman: Podman::default(),
+        }))
+    }
+
     #[test]
     fn test_create_user_validation() {
         let valid_request = CreateUserRequest {
@@ -240,17 +233,17 @@ mod tests {
 
     #[test]
     fn test_error_response_serialization() {
-        let error = ErrorResponse {
-            error: "Test error message".to_string(),
-        };
+        let error = ApiResponse::from(AppError::new(ErrorCode::UserNotFound, "Test error message"));
 
         let json = serde_json::to_string(&error).unwrap();
         assert!(json.contains("Test error message"));
+        assert!(json.contains("\"code\":\"USER_NOT_FOUND\""));
+        assert_eq!(error.status, 404);
     }
 
     #[tokio::test]
     async fn test_router_list_users_endpoint() {
-        let app = router();
+        let app = test_app();
 
         let request = Request::builder()
             .uri("/")
@@ -265,7 +258,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_router_create_user_endpoint_valid() {
-        let app = router();
+        let app = test_app();
 
         let payload = CreateUserRequest {
             username: "newuser".to_string(),
@@ -287,7 +280,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_router_create_user_endpoint_empty_username() {
-        let app = router();
+        let app = test_app();
 
         let payload = CreateUserRequest {
             username: "".to_string(),
@@ -309,7 +302,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_router_create_user_endpoint_empty_email() {
-        let app = router();
+        let app = test_app();
 
         let payload = CreateUserRequest {
             username: "testuser".to_string(),
@@ -331,7 +324,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_router_create_user_endpoint_short_password() {
-        let app = router();
+        let app = test_app();
 
         let payload = CreateUserRequest {
             username: "testuser".to_string(),
@@ -353,7 +346,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_router_get_user_endpoint() {
-        let app = router();
+        let app = test_app();
 
         let request = Request::builder()
             .uri("/1")
@@ -369,7 +362,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_router_delete_user_endpoint() {
-        let app = router();
+        let app = test_app();
 
         let request = Request::builder()
             .uri("/1")
@@ -385,7 +378,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_router_login_endpoint() {
-        let app = router();
+        let app = test_app();
 
         let payload = LoginRequest {
             username: "testuser".to_string(),
@@ -404,4 +397,28 @@ mod tests {
         // Como no hay implementación real, debe devolver UNAUTHORIZED
         assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
     }
+
+    #[tokio::test]
+    async fn test_router_login_endpoint_empty_credentials() {
+        let app = test_app();
+
+        let payload = LoginRequest {
+            username: "testuser".to_string(),
+            password: "".to_string(),
+        };
+
+        let request = Request::builder()
+            .uri("/login")
+            .method("POST")
+            .header("content-type", "application/json")
+            .body(Body::from(serde_json::to_string(&payload).unwrap()))
+            .unwrap();
+
+        let response = app.oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["message"], "Username and password are required");
+    }
 }
@@ -1,19 +1,21 @@
 mod http;
 mod models;
 mod constants;
+mod podman;
+mod storage;
+mod systemd;
 
 use axum::Router;
-use models::Error;
+use dotenv::dotenv;
+use models::{AppState, Error, QuadletDirectory};
+use std::{env::var, path::{Path, PathBuf}, str::FromStr, sync::Arc};
 use tower_http::{
     services::{
         ServeDir,
         ServeFile
     },
     trace::TraceLayer,
-    cors::{
-        CorsLayer,
-        Any,
-    },
+    cors::CorsLayer,
 };
 use tracing_subscriber::{
     EnvFilter,
@@ -40,23 +42,37 @@ async fn main() -> Result<(), Error> {
     info!("Port: {}", port);
     let secret = var("SECRET").unwrap_or("esto-es-un-secreto".to_string());
     debug!("Secret: {}", secret);
+    let generator = var("QUADLET_GENERATOR").unwrap_or(systemd::DEFAULT_GENERATOR.to_string());
+    info!("Quadlet generator: {}", generator);
+    let podman_socket = var("PODMAN_SOCKET")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| podman::default_socket(var("XDG_RUNTIME_DIR").ok().as_deref()));
+    info!("Podman socket: {}", podman_socket.display());
+
+    let state = Arc::new(AppState {
+        secret,
+        static_dir: STATIC_DIR.to_string(),
+        directories: QuadletDirectory::discover(),
+        systemd: systemd::Systemd::new(Arc::new(systemd::Systemctl)),
+        generator: systemd::Generator::new(generator),
+        podman: podman::Podman::new(podman_socket),
+    });
 
     // Configurar CORS para desarrollo
     let cors = CorsLayer::permissive();
     let api_routes = Router::new()
         .nest("/quadlets", http::quadlets_router())
+        .nest("/podman", http::podman_router())
         .nest("/users", http::users_router())
         .nest("/health", http::health_router())
         .fallback(http::fallback_404)
-        .with_state(Arc::new(AppState {
-            secret,
-            static_dir: STATIC_DIR.to_string(),
-    }));
+        .with_state(state.clone());
 
     // Crear el router principal
+    let static_dir = Path::new(&state.static_dir);
     let app = Router::new()
         .nest("/api/v1", api_routes)
-        .fallback_service(ServeDir::new(STATIC_DIR).fallback(ServeFile::new("static/index.html")))
+        .fallback_service(ServeDir::new(static_dir).fallback(ServeFile::new(static_dir.join("index.html"))))
         .layer(TraceLayer::new_for_http())
         .layer(cors);
 
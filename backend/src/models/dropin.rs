@@ -0,0 +1,321 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use super::{Quadlet, QuadletType, UnitFile};
+
+/// Archivo `.conf` de un directorio de drop-ins (`foo.container.d/`,
+/// `container.d/`...) que se aplica sobre un Quadlet
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DropIn {
+    /// Nombre relativo al directorio de quadlets, p. ej. `web.container.d/10-env.conf`
+    pub name: String,
+
+    /// Contenido del archivo
+    pub content: String,
+
+    /// Ruta completa al archivo en el sistema de archivos
+    pub path: PathBuf,
+}
+
+impl DropIn {
+    /// Crea una nueva instancia de DropIn
+    pub fn new(name: String, content: String, path: PathBuf) -> Self {
+        Self {
+            name,
+            content,
+            path,
+        }
+    }
+}
+
+/// Directorios de drop-ins que afectan a un Quadlet, de menor a mayor prioridad.
+///
+/// Para `foo-bar.container` son `container.d`, `foo-.container.d` y
+/// `foo-bar.container.d`, igual que hace Podman.
+pub fn dropin_directories(file_name: &str, kind: QuadletType) -> Vec<String> {
+    let extension = kind.extension();
+    let stem = file_name.strip_suffix(extension).unwrap_or(file_name);
+    let mut dirs = vec![format!("{}.d", kind.as_str())];
+    for (index, _) in stem.match_indices('-') {
+        dirs.push(format!("{}{}.d", &stem[..=index], extension));
+    }
+    dirs.push(format!("{}.d", file_name));
+    dirs
+}
+
+/// Determina el tipo de Quadlet al que se aplica un directorio de drop-ins
+/// (`web.container.d` o `container.d`)
+pub fn dropin_directory_type(dir: &str) -> Option<QuadletType> {
+    let base = dir.strip_suffix(".d")?;
+    let extension = match base.rsplit_once('.') {
+        Some((_, ext)) => format!(".{}", ext),
+        None => format!(".{}", base),
+    };
+    QuadletType::from_extension(&extension)
+}
+
+/// Claves que admiten varias asignaciones: cada una se suma a las anteriores.
+/// En el resto solo cuenta la última.
+const LIST_KEYS: &[&str] = &[
+    // [Unit], [Service] e [Install]
+    "After",
+    "Alias",
+    "Also",
+    "Before",
+    "BindsTo",
+    "Conflicts",
+    "Documentation",
+    "Environment",
+    "EnvironmentFile",
+    "ExecReload",
+    "ExecStartPost",
+    "ExecStartPre",
+    "ExecStop",
+    "ExecStopPost",
+    "OnFailure",
+    "PartOf",
+    "RequiredBy",
+    "Requires",
+    "Requisite",
+    "Upholds",
+    "WantedBy",
+    "Wants",
+    // Secciones de Quadlet
+    "AddCapability",
+    "AddDevice",
+    "AddHost",
+    "Annotation",
+    "ConfigMap",
+    "ContainersConfModule",
+    "DNS",
+    "DNSOption",
+    "DNSSearch",
+    "DropCapability",
+    "ExposeHostPort",
+    "Gateway",
+    "GIDMap",
+    "GlobalArgs",
+    "GroupAdd",
+    "IPRange",
+    "Label",
+    "LogOpt",
+    "Mask",
+    "Mount",
+    "Network",
+    "NetworkAlias",
+    "Options",
+    "PodmanArgs",
+    "PublishPort",
+    "Secret",
+    "SubGIDMap",
+    "SubUIDMap",
+    "Sysctl",
+    "Tmpfs",
+    "UIDMap",
+    "Ulimit",
+    "Unmask",
+    "Volume",
+];
+
+/// Asignación de la configuración efectiva junto al archivo que la define
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct EffectiveEntry {
+    pub section: String,
+    pub key: String,
+    pub value: String,
+    /// Archivo que define la asignación (el Quadlet o uno de sus drop-ins)
+    pub source: String,
+    /// Línea dentro de ese archivo
+    pub line: usize,
+    /// La clave solo admite un valor y una asignación posterior la sustituye
+    pub overridden: bool,
+}
+
+/// Configuración efectiva de un Quadlet tras aplicar sus drop-ins
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct EffectiveConfig {
+    /// Contenido combinado, tal y como lo leería el generador
+    pub content: String,
+    /// Asignaciones en orden de aplicación, con su procedencia.
+    ///
+    /// Como en systemd, una asignación vacía (`Clave=`) borra las anteriores
+    /// de la misma clave y no aparece.
+    pub entries: Vec<EffectiveEntry>,
+}
+
+impl EffectiveConfig {
+    /// Combina el Quadlet con sus drop-ins, que se aplican en el orden dado
+    pub fn merge(quadlet: &Quadlet, dropins: &[DropIn]) -> Self {
+        let mut content = String::new();
+        let mut entries = Vec::new();
+        let sources = std::iter::once((quadlet.full_name(), quadlet.content.as_str()))
+            .chain(dropins.iter().map(|d| (d.name.clone(), d.content.as_str())));
+
+        for (source, text) in sources {
+            if !content.is_empty() && !content.ends_with('\n') {
+                content.push('\n');
+            }
+            content.push_str(text);
+            for entry in UnitFile::parse(text).entries() {
+                let Some(section) = entry.section else {
+                    continue;
+                };
+                if entry.value.trim().is_empty() {
+                    entries.retain(|e: &EffectiveEntry| e.section != section || e.key != entry.key);
+                    continue;
+                }
+                entries.push(EffectiveEntry {
+                    section: section.to_string(),
+                    key: entry.key.to_string(),
+                    value: entry.value.to_string(),
+                    source: source.clone(),
+                    line: entry.line,
+                    overridden: false,
+                });
+            }
+        }
+
+        for index in 0..entries.len() {
+            let (current, later) = entries[index..].split_first().unwrap();
+            let overridden = !LIST_KEYS.contains(&current.key.as_str())
+                && later
+                    .iter()
+                    .any(|e| e.section == current.section && e.key == current.key);
+            entries[index].overridden = overridden;
+        }
+
+        Self { content, entries }
+    }
+
+    /// Asignaciones que conserva systemd: todas las de las claves con varios
+    /// valores y la última de las demás
+    pub fn values(&self) -> impl Iterator<Item = &EffectiveEntry> {
+        self.entries.iter().filter(|e| !e.overridden)
+    }
+
+    /// Asignación que gana para una clave de un solo valor
+    pub fn get(&self, section: &str, key: &str) -> Option<&EffectiveEntry> {
+        self.entries
+            .iter()
+            .rfind(|e| e.section == section && e.key == key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dropin_directories() {
+        assert_eq!(
+            dropin_directories("web.container", QuadletType::Container),
+            vec!["container.d", "web.container.d"]
+        );
+        assert_eq!(
+            dropin_directories("foo-bar-baz.network", QuadletType::Network),
+            vec![
+                "network.d",
+                "foo-.network.d",
+                "foo-bar-.network.d",
+                "foo-bar-baz.network.d"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_dropin_directory_type() {
+        assert_eq!(
+            dropin_directory_type("web.container.d"),
+            Some(QuadletType::Container)
+        );
+        assert_eq!(dropin_directory_type("volume.d"), Some(QuadletType::Volume));
+        assert_eq!(dropin_directory_type("foo-.pod.d"), Some(QuadletType::Pod));
+        assert_eq!(dropin_directory_type("web.container"), None);
+        assert_eq!(dropin_directory_type("web.txt.d"), None);
+    }
+
+    #[test]
+    fn test_effective_config_provenance() {
+        let quadlet = Quadlet::new(
+            "web".to_string(),
+            QuadletType::Container,
+            "[Container]\nImage=nginx:1.25\nPublishPort=80:80".to_string(),
+            PathBuf::from("/q/web.container"),
+        );
+        let dropins = vec![
+            DropIn::new(
+                "container.d/00-tz.conf".to_string(),
+                "[Container]\nTimezone=local\n".to_string(),
+                PathBuf::from("/q/container.d/00-tz.conf"),
+            ),
+            DropIn::new(
+                "web.container.d/10-image.conf".to_string(),
+                "# Fijar versión\n[Container]\nImage=nginx:1.27\n".to_string(),
+                PathBuf::from("/q/web.container.d/10-image.conf"),
+            ),
+        ];
+
+        let effective = EffectiveConfig::merge(&quadlet, &dropins);
+        assert_eq!(
+            UnitFile::parse(&effective.content).get("Container", "Image"),
+            Some("nginx:1.27")
+        );
+        assert_eq!(effective.entries.len(), 4);
+        let image = effective
+            .entries
+            .iter()
+            .rfind(|e| e.key == "Image")
+            .unwrap();
+        assert_eq!(image.source, "web.container.d/10-image.conf");
+        assert_eq!(image.line, 3);
+        assert_eq!(effective.entries[2].source, "container.d/00-tz.conf");
+        assert!(effective.entries[0].overridden);
+        assert!(!effective.entries[1].overridden);
+        assert_eq!(
+            effective.get("Container", "Image").unwrap().source,
+            "web.container.d/10-image.conf"
+        );
+    }
+
+    #[test]
+    fn test_effective_config_resets() {
+        let quadlet = Quadlet::new(
+            "web".to_string(),
+            QuadletType::Container,
+            "[Container]\nImage=nginx\nPod=app.pod\nVolume=a:/a\nVolume=b:/b\nNetwork=web.network\n".to_string(),
+            PathBuf::from("/q/web.container"),
+        );
+        let dropins = vec![DropIn::new(
+            "web.container.d/10-reset.conf".to_string(),
+            "[Container]\nVolume=\nVolume=c:/c\nPod=other.pod\nNetwork=db.network\n".to_string(),
+            PathBuf::from("/q/web.container.d/10-reset.conf"),
+        )];
+
+        let effective = EffectiveConfig::merge(&quadlet, &dropins);
+        let kept: Vec<(&str, &str)> = effective
+            .values()
+            .map(|e| (e.key.as_str(), e.value.as_str()))
+            .collect();
+        assert_eq!(
+            kept,
+            vec![
+                ("Image", "nginx"),
+                ("Network", "web.network"),
+                ("Volume", "c:/c"),
+                ("Pod", "other.pod"),
+                ("Network", "db.network"),
+            ]
+        );
+        // La asignación sustituida se sigue mostrando, marcada
+        let pod = effective
+            .entries
+            .iter()
+            .find(|e| e.value == "app.pod")
+            .unwrap();
+        assert!(pod.overridden);
+        assert_eq!(
+            effective.get("Container", "Pod").unwrap().value,
+            "other.pod"
+        );
+    }
+}
@@ -0,0 +1,196 @@
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt;
+
+use super::ApiResponse;
+
+/// Código de error estable que acompaña a las respuestas de error, para que
+/// los clientes no tengan que interpretar el mensaje.
+///
+/// Cada código corresponde siempre al mismo estado HTTP.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    /// Parámetros o cuerpo de la petición incorrectos
+    InvalidRequest,
+    /// Ruta inexistente
+    NotFound,
+    QuadletInvalidName,
+    QuadletInvalidExtension,
+    /// La ruta se sale del directorio de quadlets
+    QuadletInvalidPath,
+    /// La validación del contenido ha encontrado errores
+    QuadletInvalidContent,
+    QuadletTypeMismatch,
+    QuadletNotFound,
+    QuadletAlreadyExists,
+    /// Otros quadlets hacen referencia al que se quiere borrar
+    QuadletHasDependents,
+    /// El archivo ha cambiado desde la versión que indica `If-Match`
+    QuadletModified,
+    /// El origen del archivo no es propiedad del usuario
+    QuadletReadOnly,
+    /// La unidad no ha arrancado con el nuevo contenido y se ha restaurado
+    /// el anterior
+    QuadletApplyFailed,
+    /// El generador de Quadlet no ha creado la unidad a partir del archivo
+    QuadletNotGenerated,
+    DropinInvalidName,
+    DropinInvalidContent,
+    DropinNotFound,
+    RevisionInvalid,
+    RevisionNotFound,
+    HistoryFailed,
+    /// No se encuentra el directorio de quadlets (p. ej. sin `HOME`)
+    DirectoryUnavailable,
+    FilesystemError,
+    SystemdReloadFailed,
+    SystemdCommandFailed,
+    /// No se ha podido ejecutar el generador de Quadlet
+    GeneratorFailed,
+    /// No se puede conectar con el socket de la API de Podman
+    PodmanUnavailable,
+    /// La API de Podman ha respondido con un error
+    PodmanRequestFailed,
+    /// systemd no conoce la unidad, p. ej. porque el generador no la ha creado
+    UnitNotFound,
+    UserInvalid,
+    UserNotFound,
+    InvalidCredentials,
+    InternalError,
+}
+
+impl ErrorCode {
+    /// Estado HTTP con el que se responde
+    pub fn status(self) -> StatusCode {
+        match self {
+            ErrorCode::InvalidRequest
+            | ErrorCode::QuadletInvalidName
+            | ErrorCode::QuadletInvalidExtension
+            | ErrorCode::QuadletInvalidPath
+            | ErrorCode::QuadletTypeMismatch
+            | ErrorCode::DropinInvalidName
+            | ErrorCode::RevisionInvalid
+            | ErrorCode::UserInvalid => StatusCode::BAD_REQUEST,
+            ErrorCode::InvalidCredentials => StatusCode::UNAUTHORIZED,
+            ErrorCode::QuadletReadOnly => StatusCode::FORBIDDEN,
+            ErrorCode::NotFound
+            | ErrorCode::QuadletNotFound
+            | ErrorCode::DropinNotFound
+            | ErrorCode::RevisionNotFound
+            | ErrorCode::UnitNotFound
+            | ErrorCode::UserNotFound => StatusCode::NOT_FOUND,
+            ErrorCode::QuadletAlreadyExists | ErrorCode::QuadletHasDependents => {
+                StatusCode::CONFLICT
+            }
+            ErrorCode::QuadletModified => StatusCode::PRECONDITION_FAILED,
+            ErrorCode::QuadletInvalidContent
+            | ErrorCode::QuadletApplyFailed
+            | ErrorCode::QuadletNotGenerated
+            | ErrorCode::DropinInvalidContent => StatusCode::UNPROCESSABLE_ENTITY,
+            ErrorCode::HistoryFailed
+            | ErrorCode::DirectoryUnavailable
+            | ErrorCode::FilesystemError
+            | ErrorCode::SystemdReloadFailed
+            | ErrorCode::SystemdCommandFailed
+            | ErrorCode::GeneratorFailed
+            | ErrorCode::InternalError => StatusCode::INTERNAL_SERVER_ERROR,
+            ErrorCode::PodmanRequestFailed => StatusCode::BAD_GATEWAY,
+            ErrorCode::PodmanUnavailable => StatusCode::SERVICE_UNAVAILABLE,
+        }
+    }
+}
+
+/// Error de la aplicación.
+///
+/// Se convierte en un [`ApiResponse`] con el estado del código, el código en
+/// `code` y, si los hay, los detalles en `data` (p. ej. los diagnósticos de
+/// la validación).
+#[derive(Debug, Clone)]
+pub struct AppError {
+    pub code: ErrorCode,
+    pub message: String,
+    pub data: Option<Value>,
+}
+
+impl AppError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            data: None,
+        }
+    }
+
+    /// Añade detalles al error
+    pub fn with_data(mut self, data: Value) -> Self {
+        self.data = Some(data);
+        self
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl From<AppError> for ApiResponse {
+    fn from(error: AppError) -> Self {
+        ApiResponse::error(error.code, &error.message, error.data)
+    }
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        ApiResponse::from(self).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_code_serialization() {
+        assert_eq!(
+            serde_json::to_value(ErrorCode::QuadletInvalidExtension).unwrap(),
+            "QUADLET_INVALID_EXTENSION"
+        );
+        assert_eq!(
+            serde_json::to_value(ErrorCode::SystemdReloadFailed).unwrap(),
+            "SYSTEMD_RELOAD_FAILED"
+        );
+        assert_eq!(
+            ErrorCode::QuadletModified.status(),
+            StatusCode::PRECONDITION_FAILED
+        );
+    }
+
+    #[tokio::test]
+    async fn test_app_error_into_response() {
+        let error = AppError::new(
+            ErrorCode::QuadletInvalidContent,
+            "Invalid quadlet: web.container",
+        )
+        .with_data(serde_json::json!([{"code": "UNKNOWN_KEY"}]));
+        assert_eq!(error.to_string(), "Invalid quadlet: web.container");
+
+        let response = error.into_response();
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["status"], 422);
+        assert_eq!(json["code"], "QUADLET_INVALID_CONTENT");
+        assert_eq!(json["message"], "Invalid quadlet: web.container");
+        assert_eq!(json["data"][0]["code"], "UNKNOWN_KEY");
+    }
+}
@@ -0,0 +1,548 @@
+use serde::Serialize;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use super::{EffectiveConfig, Quadlet, QuadletSource, QuadletType};
+
+/// Tipo de referencia de un Quadlet a otro
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReferenceKind {
+    /// `Network=db.network`
+    Network,
+    /// `Volume=data.volume:/data`
+    Volume,
+    /// `Mount=type=volume,source=data.volume,...`
+    Mount,
+    /// `Pod=app.pod`
+    Pod,
+    /// `Image=base.image`
+    Image,
+    /// Dependencias de `[Unit]`, con el Quadlet o con su unidad
+    /// (`Requires=db.container` o `Requires=db.service`)
+    Requires,
+    Wants,
+    BindsTo,
+    PartOf,
+    After,
+    Before,
+}
+
+impl ReferenceKind {
+    /// Claves de `[Unit]` que crean dependencias
+    const UNIT_KEYS: [(&'static str, ReferenceKind); 6] = [
+        ("Requires", ReferenceKind::Requires),
+        ("Wants", ReferenceKind::Wants),
+        ("BindsTo", ReferenceKind::BindsTo),
+        ("PartOf", ReferenceKind::PartOf),
+        ("After", ReferenceKind::After),
+        ("Before", ReferenceKind::Before),
+    ];
+
+    /// Indica si el Quadlet no funciona sin el referenciado. `Wants=`,
+    /// `PartOf=`, `After=` y `Before=` solo lo piden u ordenan el arranque.
+    pub fn is_hard(&self) -> bool {
+        !matches!(
+            self,
+            ReferenceKind::Wants
+                | ReferenceKind::PartOf
+                | ReferenceKind::After
+                | ReferenceKind::Before
+        )
+    }
+
+    /// Indica si la referencia ordena el arranque. Además de `After=` y
+    /// `Before=`, el generador añade `After=` a las unidades de las redes,
+    /// volúmenes, pods e imágenes que usa un Quadlet. `Requires=`, `Wants=`,
+    /// `BindsTo=` y `PartOf=` por sí solas no ordenan nada.
+    pub fn orders(&self) -> bool {
+        !matches!(
+            self,
+            ReferenceKind::Requires
+                | ReferenceKind::Wants
+                | ReferenceKind::BindsTo
+                | ReferenceKind::PartOf
+        )
+    }
+}
+
+/// Quadlet dentro del grafo
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Node {
+    /// Nombre del archivo, que es como lo referencian los demás (`db.network`)
+    pub name: String,
+    pub relative_path: String,
+    pub kind: QuadletType,
+    pub source: QuadletSource,
+    /// Unidad que genera, p. ej. `db-network.service`
+    pub unit: String,
+}
+
+/// Referencia de un Quadlet a otro
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Edge {
+    /// Quadlet que hace la referencia
+    pub from: String,
+    /// Quadlet referenciado; en las referencias colgantes, el nombre tal y
+    /// como aparece en el archivo
+    pub to: String,
+    pub kind: ReferenceKind,
+    /// Indica si el Quadlet referenciado no existe
+    pub dangling: bool,
+}
+
+impl Edge {
+    /// Quadlet que depende del otro y Quadlet del que depende.
+    ///
+    /// Coincide con el sentido de la referencia salvo en `Before=`, que
+    /// ordena al revés: si `a.container` tiene `Before=b.service`, es `b`
+    /// quien arranca después de `a`.
+    pub fn dependency(&self) -> (&str, &str) {
+        match self.kind {
+            ReferenceKind::Before => (&self.to, &self.from),
+            _ => (&self.from, &self.to),
+        }
+    }
+}
+
+/// Grafo de dependencias entre quadlets
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct DependencyGraph {
+    pub nodes: Vec<Node>,
+    /// Referencias entre quadlets, incluidas las colgantes
+    pub edges: Vec<Edge>,
+    /// Ciclos de orden de arranque, cada uno con sus quadlets ordenados por
+    /// nombre (ver [`ReferenceKind::orders`])
+    pub cycles: Vec<Vec<String>>,
+}
+
+impl DependencyGraph {
+    /// Construye el grafo a partir de los quadlets que ve el generador.
+    ///
+    /// Los quadlets ocultos por otro con más prioridad se ignoran. En
+    /// `[Unit]` solo se tienen en cuenta las unidades de otros quadlets y los
+    /// nombres con extensión de Quadlet; el resto (`network-online.target`...)
+    /// son unidades del sistema.
+    pub fn build(quadlets: &[Quadlet]) -> Self {
+        let quadlets: Vec<&Quadlet> = quadlets.iter().filter(|q| !q.shadowed).collect();
+        let nodes: Vec<Node> = quadlets
+            .iter()
+            .map(|q| Node {
+                name: q.full_name(),
+                relative_path: q.relative_path.clone(),
+                kind: q.kind,
+                source: q.source,
+                unit: q.unit_name(),
+            })
+            .collect();
+        let names: HashSet<&str> = nodes.iter().map(|n| n.name.as_str()).collect();
+        let units: HashMap<&str, &str> = nodes
+            .iter()
+            .map(|n| (n.unit.as_str(), n.name.as_str()))
+            .collect();
+
+        let mut edges: Vec<Edge> = Vec::new();
+        for (quadlet, node) in quadlets.iter().zip(&nodes) {
+            for (kind, target) in references(quadlet) {
+                let target = units
+                    .get(target.as_str())
+                    .map(|t| t.to_string())
+                    .unwrap_or(target);
+                let is_quadlet = file_type(&target).is_some();
+                if !is_quadlet {
+                    continue;
+                }
+                let edge = Edge {
+                    from: node.name.clone(),
+                    dangling: !names.contains(target.as_str()),
+                    to: target,
+                    kind,
+                };
+                if !edges.contains(&edge) {
+                    edges.push(edge);
+                }
+            }
+        }
+
+        let cycles = cycles(&nodes, &edges);
+        Self {
+            nodes,
+            edges,
+            cycles,
+        }
+    }
+
+    /// Quadlet con el nombre de archivo indicado
+    pub fn node(&self, name: &str) -> Option<&Node> {
+        self.nodes.iter().find(|n| n.name == name)
+    }
+
+    /// Referencias a quadlets que no existen
+    pub fn dangling(&self) -> Vec<&Edge> {
+        self.edges.iter().filter(|e| e.dangling).collect()
+    }
+
+    /// Referencias de las que depende directamente el Quadlet: las que
+    /// llegan a él y sus propios `Before=`
+    pub fn incoming(&self, name: &str) -> Vec<&Edge> {
+        self.edges
+            .iter()
+            .filter(|e| !e.dangling && e.dependency().1 == name)
+            .collect()
+    }
+
+    /// Quadlets que dependen del indicado, directa o indirectamente, de
+    /// los más cercanos a los más lejanos
+    pub fn dependents(&self, name: &str) -> Vec<String> {
+        self.walk(name, |_| true)
+    }
+
+    /// Como [`DependencyGraph::dependents`], pero solo a través de
+    /// referencias sin las que el Quadlet no funciona (ver
+    /// [`ReferenceKind::is_hard`])
+    pub fn hard_dependents(&self, name: &str) -> Vec<String> {
+        self.walk(name, |e| e.kind.is_hard())
+    }
+
+    /// Recorre en anchura las referencias que cumplen `follow` hacia los
+    /// quadlets que dependen del indicado
+    fn walk(&self, name: &str, follow: impl Fn(&Edge) -> bool) -> Vec<String> {
+        let mut seen: HashSet<&str> = HashSet::from([name]);
+        let mut pending = VecDeque::from([name]);
+        let mut dependents = Vec::new();
+        while let Some(current) = pending.pop_front() {
+            for edge in self.incoming(current).into_iter().filter(|e| follow(e)) {
+                let (dependent, _) = edge.dependency();
+                if seen.insert(dependent) {
+                    dependents.push(dependent.to_string());
+                    pending.push_back(dependent);
+                }
+            }
+        }
+        dependents
+    }
+}
+
+/// Referencias de un Quadlet (con sus drop-ins), tal y como aparecen
+fn references(quadlet: &Quadlet) -> Vec<(ReferenceKind, String)> {
+    let effective = EffectiveConfig::merge(quadlet, &quadlet.dropins);
+    let section = quadlet.kind.section();
+    let mut references = Vec::new();
+    for entry in effective.values() {
+        let value = entry.value.trim();
+        if entry.section == "Unit" {
+            if let Some((_, kind)) = ReferenceKind::UNIT_KEYS
+                .iter()
+                .find(|(k, _)| *k == entry.key)
+            {
+                references.extend(value.split_whitespace().map(|v| (*kind, v.to_string())));
+            }
+            continue;
+        }
+        if entry.section != section {
+            continue;
+        }
+        let reference = match entry.key.as_str() {
+            "Network" => Some((ReferenceKind::Network, before_colon(value))),
+            "Volume" => Some((ReferenceKind::Volume, before_colon(value))),
+            "Pod" => Some((ReferenceKind::Pod, value)),
+            "Image" => Some((ReferenceKind::Image, value)),
+            "Mount" => value
+                .split(',')
+                .filter_map(|option| option.split_once('='))
+                .find(|(key, _)| matches!(key.trim(), "source" | "src"))
+                .map(|(_, source)| (ReferenceKind::Mount, source.trim())),
+            _ => None,
+        };
+        if let Some((kind, target)) = reference
+            && file_type(target).is_some()
+        {
+            references.push((kind, target.to_string()));
+        }
+    }
+    references
+}
+
+/// Parte de un valor antes de las opciones (`data.volume:/data:Z`)
+fn before_colon(value: &str) -> &str {
+    value.split(':').next().unwrap_or(value)
+}
+
+/// Tipo de Quadlet de un nombre de archivo, si tiene una extensión conocida
+fn file_type(name: &str) -> Option<QuadletType> {
+    let (stem, extension) = name.rsplit_once('.')?;
+    if stem.is_empty() || stem.contains('/') {
+        return None;
+    }
+    QuadletType::from_extension(&format!(".{}", extension))
+}
+
+/// Ciclos del grafo: componentes fuertemente conexas (Tarjan) con más de un
+/// Quadlet, o con uno que se referencia a sí mismo.
+///
+/// Solo cuentan las referencias que ordenan el arranque: systemd no tiene
+/// problema con dos unidades que se piden (`Wants=`) mutuamente, pero sí con
+/// dos que esperan la una a la otra.
+fn cycles(nodes: &[Node], edges: &[Edge]) -> Vec<Vec<String>> {
+    let index: HashMap<&str, usize> = nodes
+        .iter()
+        .enumerate()
+        .map(|(i, n)| (n.name.as_str(), i))
+        .collect();
+    let mut adjacency: Vec<Vec<usize>> = vec![Vec::new(); nodes.len()];
+    for edge in edges.iter().filter(|e| !e.dangling && e.kind.orders()) {
+        let (dependent, dependency) = edge.dependency();
+        if let (Some(&from), Some(&to)) = (index.get(dependent), index.get(dependency)) {
+            adjacency[from].push(to);
+        }
+    }
+
+    let mut tarjan = Tarjan {
+        adjacency: &adjacency,
+        counter: 0,
+        indices: vec![None; nodes.len()],
+        lowlinks: vec![0; nodes.len()],
+        stack: Vec::new(),
+        on_stack: vec![false; nodes.len()],
+        components: Vec::new(),
+    };
+    for node in 0..nodes.len() {
+        if tarjan.indices[node].is_none() {
+            tarjan.visit(node);
+        }
+    }
+
+    let mut cycles: Vec<Vec<String>> = tarjan
+        .components
+        .into_iter()
+        .filter(|c| c.len() > 1 || adjacency[c[0]].contains(&c[0]))
+        .map(|c| {
+            let mut names: Vec<String> = c.iter().map(|&i| nodes[i].name.clone()).collect();
+            names.sort();
+            names
+        })
+        .collect();
+    cycles.sort();
+    cycles
+}
+
+struct Tarjan<'a> {
+    adjacency: &'a [Vec<usize>],
+    counter: usize,
+    indices: Vec<Option<usize>>,
+    lowlinks: Vec<usize>,
+    stack: Vec<usize>,
+    on_stack: Vec<bool>,
+    components: Vec<Vec<usize>>,
+}
+
+impl Tarjan<'_> {
+    fn visit(&mut self, node: usize) {
+        self.indices[node] = Some(self.counter);
+        self.lowlinks[node] = self.counter;
+        self.counter += 1;
+        self.stack.push(node);
+        self.on_stack[node] = true;
+
+        for &next in &self.adjacency[node] {
+            match self.indices[next] {
+                None => {
+                    self.visit(next);
+                    self.lowlinks[node] = self.lowlinks[node].min(self.lowlinks[next]);
+                }
+                Some(index) if self.on_stack[next] => {
+                    self.lowlinks[node] = self.lowlinks[node].min(index);
+                }
+                Some(_) => {}
+            }
+        }
+
+        if Some(self.lowlinks[node]) == self.indices[node] {
+            let mut component = Vec::new();
+            while let Some(member) = self.stack.pop() {
+                self.on_stack[member] = false;
+                component.push(member);
+                if member == node {
+                    break;
+                }
+            }
+            self.components.push(component);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn quadlet(file_name: &str, content: &str) -> Quadlet {
+        let (name, extension) = file_name.split_once('.').unwrap();
+        let kind = QuadletType::from_extension(&format!(".{}", extension)).unwrap();
+        Quadlet::new(
+            name.to_string(),
+            kind,
+            content.to_string(),
+            PathBuf::from(format!("/q/{}", file_name)),
+        )
+    }
+
+    fn graph() -> DependencyGraph {
+        DependencyGraph::build(&[
+            quadlet(
+                "web.container",
+                "[Unit]\nAfter=network-online.target db.service\n\n[Container]\n\
+                 Image=base.image\nNetwork=app.network\nVolume=data.volume:/data:Z\n\
+                 Volume=/srv/static:/static\nPod=app.pod\n",
+            ),
+            quadlet(
+                "db.container",
+                "[Container]\nImage=docker.io/postgres:16\nNetwork=app.network\n\
+                 Mount=type=volume,source=db-data.volume,destination=/var/lib/postgresql\n",
+            ),
+            quadlet("app.network", "[Network]\n"),
+            quadlet("data.volume", "[Volume]\n"),
+            quadlet("base.image", "[Image]\nImage=docker.io/library/debian\n"),
+        ])
+    }
+
+    #[test]
+    fn test_graph_edges() {
+        let graph = graph();
+        assert_eq!(graph.nodes.len(), 5);
+        let edges: Vec<(&str, &str, ReferenceKind, bool)> = graph
+            .edges
+            .iter()
+            .map(|e| (e.from.as_str(), e.to.as_str(), e.kind, e.dangling))
+            .collect();
+        assert_eq!(
+            edges,
+            [
+                ("web.container", "db.container", ReferenceKind::After, false),
+                ("web.container", "base.image", ReferenceKind::Image, false),
+                (
+                    "web.container",
+                    "app.network",
+                    ReferenceKind::Network,
+                    false
+                ),
+                ("web.container", "data.volume", ReferenceKind::Volume, false),
+                ("web.container", "app.pod", ReferenceKind::Pod, true),
+                ("db.container", "app.network", ReferenceKind::Network, false),
+                ("db.container", "db-data.volume", ReferenceKind::Mount, true),
+            ]
+        );
+        let dangling: Vec<&str> = graph.dangling().iter().map(|e| e.to.as_str()).collect();
+        assert_eq!(dangling, ["app.pod", "db-data.volume"]);
+        assert!(graph.cycles.is_empty());
+    }
+
+    #[test]
+    fn test_graph_dependents() {
+        let graph = graph();
+        assert_eq!(
+            graph.dependents("app.network"),
+            ["web.container", "db.container"]
+        );
+        assert_eq!(graph.dependents("db.container"), ["web.container"]);
+        assert!(graph.dependents("web.container").is_empty());
+        assert_eq!(graph.incoming("data.volume").len(), 1);
+        // `web` solo tiene `After=db.service`
+        assert!(graph.hard_dependents("db.container").is_empty());
+        assert_eq!(
+            graph.hard_dependents("app.network"),
+            ["web.container", "db.container"]
+        );
+    }
+
+    #[test]
+    fn test_graph_cycles() {
+        let graph = DependencyGraph::build(&[
+            quadlet(
+                "a.container",
+                "[Unit]\nAfter=b.container\n[Container]\nImage=x\n",
+            ),
+            quadlet(
+                "b.container",
+                "[Unit]\nAfter=c.service\n[Container]\nImage=x\n",
+            ),
+            quadlet(
+                "c.container",
+                "[Unit]\nAfter=a.service\n[Container]\nImage=x\n",
+            ),
+            quadlet(
+                "d.container",
+                "[Unit]\nAfter=d.service\n[Container]\nImage=x\n",
+            ),
+            quadlet(
+                "e.container",
+                "[Unit]\nAfter=a.service\n[Container]\nImage=x\n",
+            ),
+        ]);
+        assert_eq!(
+            graph.cycles,
+            [
+                vec!["a.container", "b.container", "c.container"],
+                vec!["d.container"],
+            ]
+        );
+        assert_eq!(
+            graph.dependents("a.container"),
+            ["c.container", "e.container", "b.container"]
+        );
+    }
+
+    #[test]
+    fn test_graph_cycles_need_ordering() {
+        // Pedirse mutuamente no es un ciclo; esperarse sí, también a través
+        // del orden que añade el generador
+        let graph = DependencyGraph::build(&[
+            quadlet(
+                "a.container",
+                "[Unit]\nWants=b.service\nRequires=b.service\n[Container]\nImage=x\n",
+            ),
+            quadlet(
+                "b.container",
+                "[Unit]\nWants=a.service\nBindsTo=a.container\n[Container]\nImage=x\n",
+            ),
+            quadlet("c.container", "[Container]\nImage=x\nNetwork=app.network\n"),
+            quadlet("app.network", "[Unit]\nAfter=c.service\n[Network]\n"),
+        ]);
+        assert_eq!(graph.cycles, [vec!["app.network", "c.container"]]);
+    }
+
+    #[test]
+    fn test_graph_before_is_reversed() {
+        let graph = DependencyGraph::build(&[
+            quadlet(
+                "db.container",
+                "[Unit]\nBefore=web.service\n[Container]\nImage=x\n",
+            ),
+            quadlet(
+                "web.container",
+                "[Unit]\nAfter=db.service\n[Container]\nImage=x\n",
+            ),
+            quadlet(
+                "cache.container",
+                "[Unit]\nBefore=gone.service\n[Container]\nImage=x\n",
+            ),
+        ]);
+        // La arista conserva el sentido del archivo
+        assert_eq!(graph.edges[0].from, "db.container");
+        assert_eq!(graph.edges[0].kind, ReferenceKind::Before);
+        // pero es `web` quien depende de `db`, como con `After=`
+        assert_eq!(graph.dependents("db.container"), ["web.container"]);
+        assert_eq!(graph.incoming("db.container").len(), 2);
+        assert!(graph.dependents("web.container").is_empty());
+        assert!(graph.dependents("cache.container").is_empty());
+        assert!(graph.cycles.is_empty());
+    }
+
+    #[test]
+    fn test_graph_ignores_shadowed_quadlets() {
+        let mut shadowed = quadlet("app.network", "[Network]\n[Unit]\nAfter=gone.volume\n");
+        shadowed.shadowed = true;
+        let graph = DependencyGraph::build(&[quadlet("app.network", "[Network]\n"), shadowed]);
+        assert_eq!(graph.nodes.len(), 1);
+        assert!(graph.edges.is_empty());
+    }
+}
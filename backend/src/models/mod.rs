@@ -1,11 +1,42 @@
+mod dropin;
+mod error;
+mod graph;
 mod quadlet;
 mod response;
+mod source;
 mod paginable;
+mod search;
+mod spec;
+mod systemd;
+mod translator;
+mod unit_file;
+mod validation;
 
+pub use dropin::{DropIn, EffectiveConfig, dropin_directories, dropin_directory_type};
+pub use error::{AppError, ErrorCode};
+pub use graph::DependencyGraph;
 pub use quadlet::{Quadlet, QuadletType};
-pub use response::ApiResponse;
+pub use paginable::Paginable;
+pub use response::{ApiResponse, CustomResponse, Pagination};
+pub use search::{ContentQuery, QuadletSort, glob_match};
+pub use source::{QuadletDirectory, QuadletSource};
+pub use spec::{QuadletSpec, check_entry};
+pub use systemd::{JournalQuery, UNIT_STATE_PROPERTIES, UnitState};
+pub use translator::translate;
+pub use unit_file::{Line, UnitFile};
+pub use validation::{Diagnostic, validate};
 pub type Error = Box<dyn std::error::Error + Send + Sync>;
 pub struct AppState {
+    /// TODO: Clave para firmar las sesiones cuando haya autenticación
+    #[allow(dead_code)]
     pub secret: String,
+    /// Directorio de la interfaz web
     pub static_dir: String,
+    /// Directorios de quadlets, de mayor a menor prioridad
+    pub directories: Vec<QuadletDirectory>,
+    pub systemd: crate::systemd::Systemd,
+    /// Generador de Quadlet para las vistas previas
+    pub generator: crate::systemd::Generator,
+    /// Cliente de la API de Podman
+    pub podman: crate::podman::Podman,
 }
@@ -1,6 +1,8 @@
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
+use super::{DropIn, EffectiveConfig, QuadletSource, QuadletSpec, UnitFile, UnitState};
+
 /// Tipo de archivo Quadlet soportado
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
@@ -39,6 +41,18 @@ impl QuadletType {
         }
     }
 
+    /// Devuelve el nombre de la sección principal de este tipo, p. ej. `Container`
+    pub fn section(&self) -> &'static str {
+        match self {
+            QuadletType::Container => "Container",
+            QuadletType::Network => "Network",
+            QuadletType::Pod => "Pod",
+            QuadletType::Image => "Image",
+            QuadletType::Volume => "Volume",
+            QuadletType::Kube => "Kube",
+        }
+    }
+
     /// Devuelve una representación en string del tipo
     pub fn as_str(&self) -> &'static str {
         match self {
@@ -66,29 +80,149 @@ pub struct Quadlet {
     
     /// Ruta completa al archivo en el sistema de archivos
     pub path: PathBuf,
+
+    /// Ruta relativa al directorio de quadlets, p. ej. `apps/web.container`
+    #[serde(default)]
+    pub relative_path: String,
+
+    /// Directorio de búsqueda del que procede
+    #[serde(default)]
+    pub source: QuadletSource,
+
+    /// Indica si QuMa puede modificar el archivo
+    #[serde(default)]
+    pub writable: bool,
+
+    /// Indica si otro archivo con el mismo nombre en un origen con más
+    /// prioridad lo oculta
+    #[serde(default)]
+    pub shadowed: bool,
+
+    /// Versión del archivo en disco (hash del contenido y fecha de
+    /// modificación), para detectar ediciones concurrentes
+    #[serde(default)]
+    pub etag: String,
+
+    /// Representación tipada del contenido
+    #[serde(default)]
+    pub spec: Option<QuadletSpec>,
+
+    /// Drop-ins que se aplican sobre este Quadlet, en orden de aplicación
+    #[serde(default)]
+    pub dropins: Vec<DropIn>,
+    /// Estado de la unidad generada; solo se rellena si se pide en el
+    /// listado
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unit: Option<UnitState>,
 }
 
 impl Quadlet {
     /// Crea una nueva instancia de Quadlet
     pub fn new(name: String, kind: QuadletType, content: String, path: PathBuf) -> Self {
-        Self {
+        let relative_path = format!("{}{}", name, kind.extension());
+        let mut quadlet = Self {
             name,
             kind,
             content,
             path,
-        }
+            relative_path,
+            source: QuadletSource::User,
+            writable: true,
+            shadowed: false,
+            etag: String::new(),
+            spec: None,
+            dropins: Vec::new(),
+            unit: None,
+        };
+        quadlet.spec = Some(QuadletSpec::from_unit(kind, &quadlet.parse()));
+        quadlet
     }
 
     /// Devuelve el nombre completo del archivo (con extensión)
     pub fn full_name(&self) -> String {
         format!("{}{}", self.name, self.kind.extension())
     }
+
+    /// Devuelve la carpeta que contiene el archivo, relativa al directorio de
+    /// quadlets (cadena vacía si está en la raíz)
+    pub fn folder(&self) -> &str {
+        self.relative_path
+            .rsplit_once('/')
+            .map(|(folder, _)| folder)
+            .unwrap_or("")
+    }
+
+    /// Nombre de la unidad systemd que genera Podman a partir de este Quadlet
+    /// (`web.service`, `data-volume.service`...)
+    ///
+    /// Respeta `ServiceName=`, también si lo fija un drop-in.
+    pub fn unit_name(&self) -> String {
+        let section = self.kind.section();
+        let service_name = EffectiveConfig::merge(self, &self.dropins)
+            .get(section, "ServiceName")
+            .map(|e| e.value.trim().to_string())
+            .filter(|name| !name.is_empty());
+        if let Some(name) = service_name {
+            let name = name.strip_suffix(".service").unwrap_or(&name);
+            return format!("{}.service", name);
+        }
+        match self.kind {
+            QuadletType::Container | QuadletType::Kube => format!("{}.service", self.name),
+            _ => format!("{}-{}.service", self.name, self.kind.as_str()),
+        }
+    }
+
+    /// Interpreta el contenido como secciones, claves y valores
+    pub fn parse(&self) -> UnitFile {
+        UnitFile::parse(&self.content)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_quadlet_unit_name() {
+        let quadlet = |name: &str, kind| {
+            Quadlet::new(name.to_string(), kind, String::new(), PathBuf::new()).unit_name()
+        };
+        assert_eq!(quadlet("web", QuadletType::Container), "web.service");
+        assert_eq!(quadlet("app", QuadletType::Kube), "app.service");
+        assert_eq!(quadlet("data", QuadletType::Volume), "data-volume.service");
+        assert_eq!(quadlet("net", QuadletType::Network), "net-network.service");
+        assert_eq!(quadlet("stack", QuadletType::Pod), "stack-pod.service");
+        assert_eq!(quadlet("base", QuadletType::Image), "base-image.service");
+    }
+
+    #[test]
+    fn test_quadlet_unit_name_service_name() {
+        let mut quadlet = Quadlet::new(
+            "db".to_string(),
+            QuadletType::Volume,
+            "[Volume]\nServiceName=database-data\n".to_string(),
+            PathBuf::new(),
+        );
+        assert_eq!(quadlet.unit_name(), "database-data.service");
+
+        // Un drop-in posterior gana, y `.service` es opcional
+        quadlet.dropins.push(DropIn::new(
+            "db.volume.d/10-name.conf".to_string(),
+            "[Volume]\nServiceName=db-storage.service\n".to_string(),
+            PathBuf::new(),
+        ));
+        assert_eq!(quadlet.unit_name(), "db-storage.service");
+
+        // En otra sección no cuenta
+        let quadlet = Quadlet::new(
+            "web".to_string(),
+            QuadletType::Container,
+            "[Service]\nServiceName=other\n".to_string(),
+            PathBuf::new(),
+        );
+        assert_eq!(quadlet.unit_name(), "web.service");
+    }
+
     #[test]
     fn test_quadlet_type_extension() {
         assert_eq!(QuadletType::Container.extension(), ".container");
@@ -138,6 +272,16 @@ mod tests {
         assert_eq!(QuadletType::Image.as_str(), "image");
     }
 
+    #[test]
+    fn test_quadlet_type_section() {
+        assert_eq!(QuadletType::Container.section(), "Container");
+        assert_eq!(QuadletType::Network.section(), "Network");
+        assert_eq!(QuadletType::Volume.section(), "Volume");
+        assert_eq!(QuadletType::Kube.section(), "Kube");
+        assert_eq!(QuadletType::Pod.section(), "Pod");
+        assert_eq!(QuadletType::Image.section(), "Image");
+    }
+
     #[test]
     fn test_quadlet_creation() {
         let quadlet = Quadlet::new(
@@ -150,6 +294,8 @@ mod tests {
         assert_eq!(quadlet.name, "my-app");
         assert_eq!(quadlet.kind, QuadletType::Container);
         assert_eq!(quadlet.full_name(), "my-app.container");
+        assert_eq!(quadlet.relative_path, "my-app.container");
+        assert_eq!(quadlet.folder(), "");
     }
 
     #[test]
@@ -165,4 +311,33 @@ mod tests {
         assert!(json.contains("\"name\":\"test\""));
         assert!(json.contains("\"kind\":\"network\""));
     }
+
+    #[test]
+    fn test_quadlet_parse() {
+        let quadlet = Quadlet::new(
+            "web".to_string(),
+            QuadletType::Container,
+            "[Container]\nImage=nginx\n".to_string(),
+            PathBuf::from("/web.container"),
+        );
+
+        let unit = quadlet.parse();
+        assert_eq!(unit.get("Container", "Image"), Some("nginx"));
+        assert_eq!(unit.to_string(), quadlet.content);
+    }
+
+    #[test]
+    fn test_quadlet_spec() {
+        let quadlet = Quadlet::new(
+            "web".to_string(),
+            QuadletType::Container,
+            "[Container]\nImage=nginx\nPublishPort=80:80\n".to_string(),
+            PathBuf::from("/web.container"),
+        );
+
+        let json = serde_json::to_value(&quadlet).unwrap();
+        assert_eq!(json["spec"]["kind"], "container");
+        assert_eq!(json["spec"]["image"], "nginx");
+        assert_eq!(json["spec"]["ports"][0], "80:80");
+    }
 }
@@ -12,6 +12,7 @@ use axum::{
 };
 use serde::{Deserialize, Serialize};
 use super::paginable::Paginable;
+use super::error::ErrorCode;
 use serde_json::Value;
 
 use crate::constants::DEFAULT_LIMIT;
@@ -31,6 +32,7 @@ impl CustomResponse {
     pub fn paged(status: StatusCode, message: &str, data: Option<Value>, pagination: Pagination) -> Self {
         CustomResponse::Paged(PagedResponse::new(status, message, data, pagination))
     }
+    #[allow(dead_code)]
     pub fn empty(status: StatusCode, message: &str) -> Self {
         CustomResponse::Empty(EmptyResponse {
             status,
@@ -40,12 +42,16 @@ impl CustomResponse {
 }
 
 
+#[allow(dead_code)]
 pub type PdfResponse = (HeaderMap, Vec<u8>);
 
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ApiResponse {
     pub status: u16,
+    /// Código del error; solo en las respuestas de error
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<ErrorCode>,
     pub message: String,
     pub data: Option<Value>,
 }
@@ -54,6 +60,17 @@ impl ApiResponse {
     pub fn new(status: StatusCode, message: &str, data: Option<Value>) -> Self {
         Self {
             status: status.as_u16(),
+            code: None,
+            message: message.to_string(),
+            data,
+        }
+    }
+
+    /// Respuesta de error con el estado que corresponde al código
+    pub fn error(code: ErrorCode, message: &str, data: Option<Value>) -> Self {
+        Self {
+            status: code.status().as_u16(),
+            code: Some(code),
             message: message.to_string(),
             data,
         }
@@ -110,6 +127,8 @@ impl Pagination {
         let limit = params.limit().unwrap_or(DEFAULT_LIMIT);
         let page = params.page().unwrap_or(DEFAULT_PAGE);
         let total_pages = (count as f32 / limit as f32).ceil() as u32;
+        // La ruta base puede traer ya parámetros (filtros, orden...)
+        let separator = if base_path.contains('?') { '&' } else { '?' };
 
         Self {
             page,
@@ -117,12 +136,12 @@ impl Pagination {
             pages: total_pages,
             records: count,
             prev: if page > 1 {
-                Some(format!("{}?page={}&limit={}", base_path, page - 1, limit))
+                Some(format!("{}{}page={}&limit={}", base_path, separator, page - 1, limit))
             } else {
                 None
             },
             next: if page < total_pages {
-                Some(format!("{}?page={}&limit={}", base_path, page + 1, limit))
+                Some(format!("{}{}page={}&limit={}", base_path, separator, page + 1, limit))
             } else {
                 None
             },
@@ -163,6 +182,7 @@ pub struct EmptyResponse {
     pub message: String,
 }
 impl EmptyResponse {
+    #[allow(clippy::new_ret_no_self)]
     pub fn new(status: StatusCode, message: &str) -> Response<Body> {
         Response::builder()
             .status(status)
@@ -216,6 +236,14 @@ mod tests {
         assert_eq!(pagination.next, Some("/test?page=2&limit=10".to_string()));
     }
 
+    #[test]
+    fn test_pagination_base_path_with_query() {
+        let params = TestParams { page: Some(2), limit: Some(10) };
+        let pagination = Pagination::new(&params, 100, "/test?kind=container");
+        assert_eq!(pagination.prev, Some("/test?kind=container&page=1&limit=10".to_string()));
+        assert_eq!(pagination.next, Some("/test?kind=container&page=3&limit=10".to_string()));
+    }
+
     #[test]
     fn test_pagination_last_page() {
         let params = TestParams { page: Some(10), limit: Some(10) };
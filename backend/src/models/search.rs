@@ -0,0 +1,247 @@
+use std::cmp::Ordering;
+
+use super::{EffectiveConfig, Quadlet};
+
+/// Búsqueda por contenido: `Image~=nginx` (contiene) o `Image=nginx`
+/// (igual). La clave puede llevar la sección: `Container.Image~=nginx`.
+///
+/// Se busca en la configuración efectiva, así que también cuentan los
+/// drop-ins.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContentQuery {
+    pub section: Option<String>,
+    pub key: String,
+    pub value: String,
+    /// `true` para `~=` (subcadena, sin distinguir mayúsculas)
+    pub contains: bool,
+}
+
+impl ContentQuery {
+    /// Interpreta una expresión `[Sección.]Clave~=valor` o `[Sección.]Clave=valor`
+    pub fn parse(query: &str) -> Result<Self, String> {
+        let (key, value, contains) = match query.split_once("~=") {
+            Some((key, value)) => (key, value, true),
+            None => match query.split_once('=') {
+                Some((key, value)) => (key, value, false),
+                None => return Err(format!("Invalid query: {} (expected Key~=value)", query)),
+            },
+        };
+        let (section, key) = match key.trim().rsplit_once('.') {
+            Some((section, key)) => (Some(section.to_string()), key),
+            None => (None, key.trim()),
+        };
+        if key.is_empty() || section.as_deref().is_some_and(str::is_empty) {
+            return Err(format!("Invalid query: {} (expected Key~=value)", query));
+        }
+        Ok(Self {
+            section,
+            key: key.to_string(),
+            value: value.trim().to_string(),
+            contains,
+        })
+    }
+
+    /// Indica si el Quadlet (con sus drop-ins) tiene alguna asignación que
+    /// cumpla la búsqueda
+    pub fn matches(&self, quadlet: &Quadlet) -> bool {
+        let value = self.value.to_lowercase();
+        EffectiveConfig::merge(quadlet, &quadlet.dropins)
+            .values()
+            .filter(|e| e.key == self.key)
+            .filter(|e| self.section.as_ref().is_none_or(|s| *s == e.section))
+            .any(|e| {
+                if self.contains {
+                    e.value.to_lowercase().contains(&value)
+                } else {
+                    e.value == self.value
+                }
+            })
+    }
+}
+
+/// Orden del listado de quadlets: `path` (por defecto), `name`, `kind`,
+/// `folder` o `source`, con `-` delante para invertirlo
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QuadletSort {
+    field: SortField,
+    descending: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SortField {
+    Path,
+    Name,
+    Kind,
+    Folder,
+    Source,
+}
+
+impl QuadletSort {
+    /// Interpreta el parámetro `sort`
+    pub fn parse(sort: &str) -> Result<Self, String> {
+        let (descending, field) = match sort.strip_prefix('-') {
+            Some(field) => (true, field),
+            None => (false, sort),
+        };
+        let field = match field {
+            "path" => SortField::Path,
+            "name" => SortField::Name,
+            "kind" => SortField::Kind,
+            "folder" => SortField::Folder,
+            "source" => SortField::Source,
+            _ => return Err(format!("Invalid sort: {}", sort)),
+        };
+        Ok(Self { field, descending })
+    }
+
+    /// Ordena la lista; a igualdad se desempata por ruta relativa
+    pub fn sort(&self, quadlets: &mut [Quadlet]) {
+        quadlets.sort_by(|a, b| {
+            let ordering = match self.field {
+                SortField::Path => Ordering::Equal,
+                SortField::Name => a.full_name().cmp(&b.full_name()),
+                SortField::Kind => a.kind.as_str().cmp(b.kind.as_str()),
+                SortField::Folder => a.folder().cmp(b.folder()),
+                SortField::Source => (a.source as u8).cmp(&(b.source as u8)),
+            }
+            .then_with(|| a.relative_path.cmp(&b.relative_path));
+            if self.descending { ordering.reverse() } else { ordering }
+        });
+    }
+}
+
+impl Default for QuadletSort {
+    fn default() -> Self {
+        Self {
+            field: SortField::Path,
+            descending: false,
+        }
+    }
+}
+
+/// Comprueba si `text` encaja con un patrón con comodines `*` y `?`, sin
+/// distinguir mayúsculas
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // Última posición de `*` en el patrón y del texto cuando se encontró
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = star {
+            p = star_p + 1;
+            t = star_t + 1;
+            star = Some((star_p, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{DropIn, QuadletSource, QuadletType};
+    use std::path::PathBuf;
+
+    fn quadlet(relative_path: &str, content: &str) -> Quadlet {
+        let file_name = relative_path.rsplit('/').next().unwrap();
+        let (name, _) = file_name.rsplit_once('.').unwrap();
+        let mut quadlet = Quadlet::new(
+            name.to_string(),
+            QuadletType::Container,
+            content.to_string(),
+            PathBuf::from(relative_path),
+        );
+        quadlet.relative_path = relative_path.to_string();
+        quadlet
+    }
+
+    #[test]
+    fn test_content_query_parse() {
+        assert_eq!(
+            ContentQuery::parse("Image~=nginx").unwrap(),
+            ContentQuery {
+                section: None,
+                key: "Image".to_string(),
+                value: "nginx".to_string(),
+                contains: true,
+            }
+        );
+        let query = ContentQuery::parse("Container.PublishPort=8080:80").unwrap();
+        assert_eq!(query.section.as_deref(), Some("Container"));
+        assert!(!query.contains);
+        assert!(ContentQuery::parse("Image").is_err());
+        assert!(ContentQuery::parse("~=nginx").is_err());
+        assert!(ContentQuery::parse(".Image=nginx").is_err());
+    }
+
+    #[test]
+    fn test_content_query_matches() {
+        let mut web = quadlet("web.container", "[Container]\nImage=docker.io/NGINX:1.27\n");
+        assert!(ContentQuery::parse("Image~=nginx").unwrap().matches(&web));
+        assert!(ContentQuery::parse("Container.Image~=nginx").unwrap().matches(&web));
+        assert!(!ContentQuery::parse("Service.Image~=nginx").unwrap().matches(&web));
+        assert!(!ContentQuery::parse("Image=nginx").unwrap().matches(&web));
+        assert!(!ContentQuery::parse("PublishPort~=80").unwrap().matches(&web));
+
+        web.dropins.push(DropIn::new(
+            "web.container.d/ports.conf".to_string(),
+            "[Container]\nPublishPort=8080:80\n".to_string(),
+            PathBuf::from("web.container.d/ports.conf"),
+        ));
+        assert!(ContentQuery::parse("PublishPort=8080:80").unwrap().matches(&web));
+
+        // Solo cuentan los valores que conserva systemd
+        web.dropins.push(DropIn::new(
+            "web.container.d/image.conf".to_string(),
+            "[Container]\nImage=quay.io/caddy\nPublishPort=\n".to_string(),
+            PathBuf::from("web.container.d/image.conf"),
+        ));
+        assert!(!ContentQuery::parse("Image~=nginx").unwrap().matches(&web));
+        assert!(ContentQuery::parse("Image~=caddy").unwrap().matches(&web));
+        assert!(!ContentQuery::parse("PublishPort=8080:80").unwrap().matches(&web));
+    }
+
+    #[test]
+    fn test_quadlet_sort() {
+        let mut quadlets = vec![
+            quadlet("b/web.container", ""),
+            quadlet("a/db.container", ""),
+            quadlet("cache.container", ""),
+        ];
+        quadlets[2].source = QuadletSource::Runtime;
+        let order = |quadlets: &[Quadlet]| -> Vec<String> {
+            quadlets.iter().map(|q| q.relative_path.clone()).collect()
+        };
+
+        QuadletSort::parse("name").unwrap().sort(&mut quadlets);
+        assert_eq!(order(&quadlets), ["cache.container", "a/db.container", "b/web.container"]);
+        QuadletSort::parse("-path").unwrap().sort(&mut quadlets);
+        assert_eq!(order(&quadlets), ["cache.container", "b/web.container", "a/db.container"]);
+        QuadletSort::parse("source").unwrap().sort(&mut quadlets);
+        assert_eq!(order(&quadlets), ["cache.container", "a/db.container", "b/web.container"]);
+        QuadletSort::default().sort(&mut quadlets);
+        assert_eq!(order(&quadlets), ["a/db.container", "b/web.container", "cache.container"]);
+        assert!(QuadletSort::parse("size").is_err());
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("web*", "web.container"));
+        assert!(glob_match("*.CONTAINER", "web.container"));
+        assert!(glob_match("w?b.*", "web.container"));
+        assert!(glob_match("*b*c*", "abc"));
+        assert!(glob_match("*", ""));
+        assert!(!glob_match("web", "web.container"));
+        assert!(!glob_match("*.volume", "web.container"));
+        assert!(!glob_match("a*b", "acd"));
+    }
+}
@@ -0,0 +1,149 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Directorio de búsqueda del que procede un Quadlet
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QuadletSource {
+    /// `$XDG_RUNTIME_DIR/containers/systemd` (transitorio)
+    Runtime,
+    /// `$XDG_CONFIG_HOME/containers/systemd` o `~/.config/containers/systemd`
+    #[default]
+    User,
+    /// `/etc/containers/systemd/users/$UID`
+    AdminUser,
+    /// `/etc/containers/systemd/users`
+    AdminUsers,
+}
+
+impl QuadletSource {
+    /// Indica si QuMa puede escribir en este origen
+    pub fn writable(&self) -> bool {
+        matches!(self, QuadletSource::Runtime | QuadletSource::User)
+    }
+
+    /// Devuelve una representación en string del origen
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            QuadletSource::Runtime => "runtime",
+            QuadletSource::User => "user",
+            QuadletSource::AdminUser => "admin_user",
+            QuadletSource::AdminUsers => "admin_users",
+        }
+    }
+}
+
+/// Directorio de búsqueda de quadlets
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct QuadletDirectory {
+    pub source: QuadletSource,
+    pub path: PathBuf,
+}
+
+impl QuadletDirectory {
+    /// Directorios que lee Podman para un usuario rootless, de mayor a menor
+    /// prioridad: si el mismo archivo aparece en varios, gana el primero
+    pub fn search_paths(
+        runtime_dir: Option<&str>,
+        config_home: Option<&str>,
+        home: Option<&str>,
+        uid: Option<u32>,
+    ) -> Vec<Self> {
+        let mut dirs = Vec::new();
+        let non_empty = |v: Option<&str>| v.filter(|v| !v.is_empty()).map(PathBuf::from);
+
+        if let Some(runtime_dir) = non_empty(runtime_dir) {
+            dirs.push(Self {
+                source: QuadletSource::Runtime,
+                path: runtime_dir.join("containers/systemd"),
+            });
+        }
+        let config_home = non_empty(config_home).or_else(|| non_empty(home).map(|h| h.join(".config")));
+        if let Some(config_home) = config_home {
+            dirs.push(Self {
+                source: QuadletSource::User,
+                path: config_home.join("containers/systemd"),
+            });
+        }
+        if let Some(uid) = uid {
+            dirs.push(Self {
+                source: QuadletSource::AdminUser,
+                path: PathBuf::from(format!("/etc/containers/systemd/users/{}", uid)),
+            });
+        }
+        dirs.push(Self {
+            source: QuadletSource::AdminUsers,
+            path: PathBuf::from("/etc/containers/systemd/users"),
+        });
+        dirs
+    }
+
+    /// Directorios de búsqueda según el entorno del proceso
+    pub fn discover() -> Vec<Self> {
+        use std::os::unix::fs::MetadataExt;
+
+        let var = |name: &str| std::env::var(name).ok();
+        let uid = std::fs::metadata("/proc/self").map(|m| m.uid()).ok();
+        Self::search_paths(
+            var("XDG_RUNTIME_DIR").as_deref(),
+            var("XDG_CONFIG_HOME").as_deref(),
+            var("HOME").as_deref(),
+            uid,
+        )
+    }
+
+    /// Indica si QuMa puede escribir en este directorio
+    pub fn writable(&self) -> bool {
+        self.source.writable()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_search_paths_order() {
+        let dirs = QuadletDirectory::search_paths(
+            Some("/run/user/1000"),
+            Some("/home/ana/.cfg"),
+            Some("/home/ana"),
+            Some(1000),
+        );
+        let paths: Vec<(QuadletSource, &str)> = dirs
+            .iter()
+            .map(|d| (d.source, d.path.to_str().unwrap()))
+            .collect();
+        assert_eq!(
+            paths,
+            vec![
+                (QuadletSource::Runtime, "/run/user/1000/containers/systemd"),
+                (QuadletSource::User, "/home/ana/.cfg/containers/systemd"),
+                (QuadletSource::AdminUser, "/etc/containers/systemd/users/1000"),
+                (QuadletSource::AdminUsers, "/etc/containers/systemd/users"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_search_paths_fallback_to_home() {
+        let dirs = QuadletDirectory::search_paths(None, Some(""), Some("/home/ana"), None);
+        assert_eq!(dirs.len(), 2);
+        assert_eq!(dirs[0].source, QuadletSource::User);
+        assert_eq!(dirs[0].path, PathBuf::from("/home/ana/.config/containers/systemd"));
+    }
+
+    #[test]
+    fn test_source_writable() {
+        assert!(QuadletSource::Runtime.writable());
+        assert!(QuadletSource::User.writable());
+        assert!(!QuadletSource::AdminUser.writable());
+        assert!(!QuadletSource::AdminUsers.writable());
+    }
+
+    #[test]
+    fn test_source_serialization() {
+        assert_eq!(serde_json::to_string(&QuadletSource::AdminUser).unwrap(), "\"admin_user\"");
+        assert_eq!(QuadletSource::AdminUsers.as_str(), "admin_users");
+    }
+}
@@ -0,0 +1,1041 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+
+use super::{QuadletType, UnitFile};
+
+/// Opciones comunes de la sección `[Unit]`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UnitOptions {
+    pub description: Option<String>,
+    pub requires: Vec<String>,
+    pub wants: Vec<String>,
+    pub after: Vec<String>,
+    pub before: Vec<String>,
+}
+
+/// Opciones comunes de la sección `[Service]`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ServiceOptions {
+    pub restart: Option<String>,
+    pub timeout_start_sec: Option<String>,
+}
+
+/// Opciones comunes de la sección `[Install]`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InstallOptions {
+    pub wanted_by: Vec<String>,
+}
+
+/// Asignación que no tiene un campo tipado propio
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExtraEntry {
+    pub section: String,
+    pub key: String,
+    pub value: String,
+}
+
+/// Representación tipada de un archivo `.container`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ContainerSpec {
+    pub image: Option<String>,
+    pub container_name: Option<String>,
+    pub exec: Option<String>,
+    pub entrypoint: Option<String>,
+    pub ports: Vec<String>,
+    pub volumes: Vec<String>,
+    pub mounts: Vec<String>,
+    pub env: BTreeMap<String, String>,
+    pub env_files: Vec<String>,
+    pub labels: BTreeMap<String, String>,
+    pub networks: Vec<String>,
+    pub network_aliases: Vec<String>,
+    pub pod: Option<String>,
+    pub user: Option<String>,
+    pub working_dir: Option<String>,
+    pub secrets: Vec<String>,
+    pub devices: Vec<String>,
+    pub add_capabilities: Vec<String>,
+    pub drop_capabilities: Vec<String>,
+    pub read_only: Option<bool>,
+    pub no_new_privileges: Option<bool>,
+    pub health_cmd: Option<String>,
+    pub auto_update: Option<String>,
+    pub pull: Option<String>,
+    pub podman_args: Vec<String>,
+    pub unit: UnitOptions,
+    pub service: ServiceOptions,
+    pub install: InstallOptions,
+    pub extra: Vec<ExtraEntry>,
+}
+
+/// Representación tipada de un archivo `.network`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NetworkSpec {
+    pub network_name: Option<String>,
+    pub driver: Option<String>,
+    pub subnets: Vec<String>,
+    pub gateways: Vec<String>,
+    pub ip_ranges: Vec<String>,
+    pub internal: Option<bool>,
+    pub ipv6: Option<bool>,
+    pub disable_dns: Option<bool>,
+    pub dns: Vec<String>,
+    pub options: Vec<String>,
+    pub labels: BTreeMap<String, String>,
+    pub podman_args: Vec<String>,
+    pub unit: UnitOptions,
+    pub service: ServiceOptions,
+    pub install: InstallOptions,
+    pub extra: Vec<ExtraEntry>,
+}
+
+/// Representación tipada de un archivo `.volume`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VolumeSpec {
+    pub volume_name: Option<String>,
+    pub driver: Option<String>,
+    pub device: Option<String>,
+    #[serde(rename = "type")]
+    pub fs_type: Option<String>,
+    pub options: Option<String>,
+    pub copy: Option<bool>,
+    pub user: Option<String>,
+    pub group: Option<String>,
+    pub image: Option<String>,
+    pub labels: BTreeMap<String, String>,
+    pub podman_args: Vec<String>,
+    pub unit: UnitOptions,
+    pub service: ServiceOptions,
+    pub install: InstallOptions,
+    pub extra: Vec<ExtraEntry>,
+}
+
+/// Representación tipada de un archivo `.pod`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PodSpec {
+    pub pod_name: Option<String>,
+    pub ports: Vec<String>,
+    pub networks: Vec<String>,
+    pub network_aliases: Vec<String>,
+    pub volumes: Vec<String>,
+    pub labels: BTreeMap<String, String>,
+    pub hostname: Option<String>,
+    pub podman_args: Vec<String>,
+    pub unit: UnitOptions,
+    pub service: ServiceOptions,
+    pub install: InstallOptions,
+    pub extra: Vec<ExtraEntry>,
+}
+
+/// Representación tipada de un archivo `.kube`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KubeSpec {
+    pub yaml: Option<String>,
+    pub config_maps: Vec<String>,
+    pub networks: Vec<String>,
+    pub ports: Vec<String>,
+    pub auto_update: Vec<String>,
+    pub exit_code_propagation: Option<String>,
+    pub user_ns: Option<String>,
+    pub podman_args: Vec<String>,
+    pub unit: UnitOptions,
+    pub service: ServiceOptions,
+    pub install: InstallOptions,
+    pub extra: Vec<ExtraEntry>,
+}
+
+/// Representación tipada de un archivo `.image`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ImageSpec {
+    pub image: Option<String>,
+    pub image_tag: Option<String>,
+    pub all_tags: Option<bool>,
+    pub arch: Option<String>,
+    pub os: Option<String>,
+    pub variant: Option<String>,
+    pub auth_file: Option<String>,
+    pub creds: Option<String>,
+    pub tls_verify: Option<bool>,
+    pub policy: Option<String>,
+    pub podman_args: Vec<String>,
+    pub unit: UnitOptions,
+    pub service: ServiceOptions,
+    pub install: InstallOptions,
+    pub extra: Vec<ExtraEntry>,
+}
+
+/// Representación tipada de un Quadlet, etiquetada por su tipo
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum QuadletSpec {
+    Container(Box<ContainerSpec>),
+    Network(NetworkSpec),
+    Volume(VolumeSpec),
+    Kube(KubeSpec),
+    Pod(PodSpec),
+    Image(ImageSpec),
+}
+
+impl QuadletSpec {
+    /// Construye la representación tipada a partir de un archivo ya interpretado
+    pub fn from_unit(kind: QuadletType, unit: &UnitFile) -> Self {
+        let mut r = Reader::new(unit, kind.section());
+        match kind {
+            QuadletType::Container => QuadletSpec::Container(Box::new(ContainerSpec {
+                image: r.one("Image"),
+                container_name: r.one("ContainerName"),
+                exec: r.one("Exec"),
+                entrypoint: r.one("Entrypoint"),
+                ports: r.all("PublishPort"),
+                volumes: r.all("Volume"),
+                mounts: r.all("Mount"),
+                env: r.pairs("Environment"),
+                env_files: r.all("EnvironmentFile"),
+                labels: r.pairs("Label"),
+                networks: r.all("Network"),
+                network_aliases: r.all("NetworkAlias"),
+                pod: r.one("Pod"),
+                user: r.one("User"),
+                working_dir: r.one("WorkingDir"),
+                secrets: r.all("Secret"),
+                devices: r.all("AddDevice"),
+                add_capabilities: r.words("AddCapability"),
+                drop_capabilities: r.words("DropCapability"),
+                read_only: r.boolean("ReadOnly"),
+                no_new_privileges: r.boolean("NoNewPrivileges"),
+                health_cmd: r.one("HealthCmd"),
+                auto_update: r.one("AutoUpdate"),
+                pull: r.one("Pull"),
+                podman_args: r.all("PodmanArgs"),
+                unit: r.unit(),
+                service: r.service(),
+                install: r.install(),
+                extra: r.rest(),
+            })),
+            QuadletType::Network => QuadletSpec::Network(NetworkSpec {
+                network_name: r.one("NetworkName"),
+                driver: r.one("Driver"),
+                subnets: r.all("Subnet"),
+                gateways: r.all("Gateway"),
+                ip_ranges: r.all("IPRange"),
+                internal: r.boolean("Internal"),
+                ipv6: r.boolean("IPv6"),
+                disable_dns: r.boolean("DisableDNS"),
+                dns: r.all("DNS"),
+                options: r.all("Options"),
+                labels: r.pairs("Label"),
+                podman_args: r.all("PodmanArgs"),
+                unit: r.unit(),
+                service: r.service(),
+                install: r.install(),
+                extra: r.rest(),
+            }),
+            QuadletType::Volume => QuadletSpec::Volume(VolumeSpec {
+                volume_name: r.one("VolumeName"),
+                driver: r.one("Driver"),
+                device: r.one("Device"),
+                fs_type: r.one("Type"),
+                options: r.one("Options"),
+                copy: r.boolean("Copy"),
+                user: r.one("User"),
+                group: r.one("Group"),
+                image: r.one("Image"),
+                labels: r.pairs("Label"),
+                podman_args: r.all("PodmanArgs"),
+                unit: r.unit(),
+                service: r.service(),
+                install: r.install(),
+                extra: r.rest(),
+            }),
+            QuadletType::Kube => QuadletSpec::Kube(KubeSpec {
+                yaml: r.one("Yaml"),
+                config_maps: r.all("ConfigMap"),
+                networks: r.all("Network"),
+                ports: r.all("PublishPort"),
+                auto_update: r.all("AutoUpdate"),
+                exit_code_propagation: r.one("ExitCodePropagation"),
+                user_ns: r.one("UserNS"),
+                podman_args: r.all("PodmanArgs"),
+                unit: r.unit(),
+                service: r.service(),
+                install: r.install(),
+                extra: r.rest(),
+            }),
+            QuadletType::Pod => QuadletSpec::Pod(PodSpec {
+                pod_name: r.one("PodName"),
+                ports: r.all("PublishPort"),
+                networks: r.all("Network"),
+                network_aliases: r.all("NetworkAlias"),
+                volumes: r.all("Volume"),
+                labels: r.pairs("Label"),
+                hostname: r.one("HostName"),
+                podman_args: r.all("PodmanArgs"),
+                unit: r.unit(),
+                service: r.service(),
+                install: r.install(),
+                extra: r.rest(),
+            }),
+            QuadletType::Image => QuadletSpec::Image(ImageSpec {
+                image: r.one("Image"),
+                image_tag: r.one("ImageTag"),
+                all_tags: r.boolean("AllTags"),
+                arch: r.one("Arch"),
+                os: r.one("OS"),
+                variant: r.one("Variant"),
+                auth_file: r.one("AuthFile"),
+                creds: r.one("Creds"),
+                tls_verify: r.boolean("TLSVerify"),
+                policy: r.one("Policy"),
+                podman_args: r.all("PodmanArgs"),
+                unit: r.unit(),
+                service: r.service(),
+                install: r.install(),
+                extra: r.rest(),
+            }),
+        }
+    }
+
+    /// Devuelve el tipo de Quadlet que representa
+    pub fn kind(&self) -> QuadletType {
+        match self {
+            QuadletSpec::Container(_) => QuadletType::Container,
+            QuadletSpec::Network(_) => QuadletType::Network,
+            QuadletSpec::Volume(_) => QuadletType::Volume,
+            QuadletSpec::Kube(_) => QuadletType::Kube,
+            QuadletSpec::Pod(_) => QuadletType::Pod,
+            QuadletSpec::Image(_) => QuadletType::Image,
+        }
+    }
+
+    /// Genera el archivo INI canónico: `[Unit]`, la sección del tipo,
+    /// `[Service]`, `[Install]` y el resto de secciones, con las claves
+    /// siempre en el mismo orden.
+    ///
+    /// Falla si algún valor tiene saltos de línea o alguna sección o clave
+    /// de `extra` no es válida, porque añadirían líneas al archivo.
+    pub fn render(&self) -> Result<String, String> {
+        let mut w = Writer::new(self.kind().section());
+        match self {
+            QuadletSpec::Container(c) => {
+                w.unit(&c.unit);
+                w.one("Image", &c.image);
+                w.one("ContainerName", &c.container_name);
+                w.one("Exec", &c.exec);
+                w.one("Entrypoint", &c.entrypoint);
+                w.all("PublishPort", &c.ports);
+                w.all("Volume", &c.volumes);
+                w.all("Mount", &c.mounts);
+                w.pairs("Environment", &c.env);
+                w.all("EnvironmentFile", &c.env_files);
+                w.pairs("Label", &c.labels);
+                w.all("Network", &c.networks);
+                w.all("NetworkAlias", &c.network_aliases);
+                w.one("Pod", &c.pod);
+                w.one("User", &c.user);
+                w.one("WorkingDir", &c.working_dir);
+                w.all("Secret", &c.secrets);
+                w.all("AddDevice", &c.devices);
+                w.all("AddCapability", &c.add_capabilities);
+                w.all("DropCapability", &c.drop_capabilities);
+                w.boolean("ReadOnly", c.read_only);
+                w.boolean("NoNewPrivileges", c.no_new_privileges);
+                w.one("HealthCmd", &c.health_cmd);
+                w.one("AutoUpdate", &c.auto_update);
+                w.one("Pull", &c.pull);
+                w.all("PodmanArgs", &c.podman_args);
+                w.common(&c.service, &c.install, &c.extra);
+            }
+            QuadletSpec::Network(n) => {
+                w.unit(&n.unit);
+                w.one("NetworkName", &n.network_name);
+                w.one("Driver", &n.driver);
+                w.all("Subnet", &n.subnets);
+                w.all("Gateway", &n.gateways);
+                w.all("IPRange", &n.ip_ranges);
+                w.boolean("Internal", n.internal);
+                w.boolean("IPv6", n.ipv6);
+                w.boolean("DisableDNS", n.disable_dns);
+                w.all("DNS", &n.dns);
+                w.all("Options", &n.options);
+                w.pairs("Label", &n.labels);
+                w.all("PodmanArgs", &n.podman_args);
+                w.common(&n.service, &n.install, &n.extra);
+            }
+            QuadletSpec::Volume(v) => {
+                w.unit(&v.unit);
+                w.one("VolumeName", &v.volume_name);
+                w.one("Driver", &v.driver);
+                w.one("Device", &v.device);
+                w.one("Type", &v.fs_type);
+                w.one("Options", &v.options);
+                w.boolean("Copy", v.copy);
+                w.one("User", &v.user);
+                w.one("Group", &v.group);
+                w.one("Image", &v.image);
+                w.pairs("Label", &v.labels);
+                w.all("PodmanArgs", &v.podman_args);
+                w.common(&v.service, &v.install, &v.extra);
+            }
+            QuadletSpec::Kube(k) => {
+                w.unit(&k.unit);
+                w.one("Yaml", &k.yaml);
+                w.all("ConfigMap", &k.config_maps);
+                w.all("Network", &k.networks);
+                w.all("PublishPort", &k.ports);
+                w.all("AutoUpdate", &k.auto_update);
+                w.one("ExitCodePropagation", &k.exit_code_propagation);
+                w.one("UserNS", &k.user_ns);
+                w.all("PodmanArgs", &k.podman_args);
+                w.common(&k.service, &k.install, &k.extra);
+            }
+            QuadletSpec::Pod(p) => {
+                w.unit(&p.unit);
+                w.one("PodName", &p.pod_name);
+                w.all("PublishPort", &p.ports);
+                w.all("Network", &p.networks);
+                w.all("NetworkAlias", &p.network_aliases);
+                w.all("Volume", &p.volumes);
+                w.pairs("Label", &p.labels);
+                w.one("HostName", &p.hostname);
+                w.all("PodmanArgs", &p.podman_args);
+                w.common(&p.service, &p.install, &p.extra);
+            }
+            QuadletSpec::Image(i) => {
+                w.unit(&i.unit);
+                w.one("Image", &i.image);
+                w.one("ImageTag", &i.image_tag);
+                w.boolean("AllTags", i.all_tags);
+                w.one("Arch", &i.arch);
+                w.one("OS", &i.os);
+                w.one("Variant", &i.variant);
+                w.one("AuthFile", &i.auth_file);
+                w.one("Creds", &i.creds);
+                w.boolean("TLSVerify", i.tls_verify);
+                w.one("Policy", &i.policy);
+                w.all("PodmanArgs", &i.podman_args);
+                w.common(&i.service, &i.install, &i.extra);
+            }
+        }
+        w.finish()
+    }
+}
+
+/// Clave, valor y si la asignación viene de `extra`
+type WriterEntry = (String, String, bool);
+
+/// Construye un archivo INI con secciones en orden fijo
+struct Writer {
+    main: &'static str,
+    sections: Vec<(String, Vec<WriterEntry>)>,
+    /// Primera asignación que no se puede escribir
+    error: Option<String>,
+}
+
+impl Writer {
+    fn new(main: &'static str) -> Self {
+        let sections = ["Unit", main, "Service", "Install"]
+            .iter()
+            .map(|s| (s.to_string(), Vec::new()))
+            .collect();
+        Self {
+            main,
+            sections,
+            error: None,
+        }
+    }
+
+    fn push_in(&mut self, section: &str, key: &str, value: &str) {
+        if let Some(entries) = self.entries(section, key, value) {
+            entries.push((key.to_string(), value.to_string(), false));
+        }
+    }
+
+    /// Escribe una asignación de `extra`.
+    ///
+    /// Si la clave también tiene campo tipado (valores repetidos o que no se
+    /// han podido interpretar) va delante de él, para que al volver a leer
+    /// el archivo siga ganando el valor tipado.
+    fn push_extra(&mut self, entry: &ExtraEntry) {
+        if let Some(entries) = self.entries(&entry.section, &entry.key, &entry.value) {
+            let position = entries
+                .iter()
+                .position(|(key, _, extra)| !extra && *key == entry.key)
+                .unwrap_or(entries.len());
+            entries.insert(position, (entry.key.clone(), entry.value.clone(), true));
+        }
+    }
+
+    /// Asignaciones de una sección, creándola si hace falta, o `None` si la
+    /// asignación no se puede escribir
+    fn entries(&mut self, section: &str, key: &str, value: &str) -> Option<&mut Vec<WriterEntry>> {
+        if let Err(error) = check_entry(section, key, Some(value)) {
+            self.error.get_or_insert(error);
+            return None;
+        }
+        let index = match self.sections.iter().position(|(name, _)| name == section) {
+            Some(index) => index,
+            None => {
+                self.sections.push((section.to_string(), Vec::new()));
+                self.sections.len() - 1
+            }
+        };
+        Some(&mut self.sections[index].1)
+    }
+
+    fn one(&mut self, key: &str, value: &Option<String>) {
+        if let Some(value) = value {
+            self.push_in(self.main, key, value);
+        }
+    }
+
+    fn all(&mut self, key: &str, values: &[String]) {
+        for value in values {
+            self.push_in(self.main, key, value);
+        }
+    }
+
+    fn boolean(&mut self, key: &str, value: Option<bool>) {
+        if let Some(value) = value {
+            self.push_in(self.main, key, if value { "true" } else { "false" });
+        }
+    }
+
+    fn pairs(&mut self, key: &str, pairs: &BTreeMap<String, String>) {
+        for (k, v) in pairs {
+            self.push_in(self.main, key, &quote_word(&format!("{}={}", k, v)));
+        }
+    }
+
+    fn unit(&mut self, unit: &UnitOptions) {
+        if let Some(description) = &unit.description {
+            self.push_in("Unit", "Description", description);
+        }
+        for (key, values) in [
+            ("Requires", &unit.requires),
+            ("Wants", &unit.wants),
+            ("After", &unit.after),
+            ("Before", &unit.before),
+        ] {
+            for value in values {
+                self.push_in("Unit", key, value);
+            }
+        }
+    }
+
+    /// Escribe `[Service]`, `[Install]` y las claves sin campo propio
+    fn common(&mut self, service: &ServiceOptions, install: &InstallOptions, extra: &[ExtraEntry]) {
+        let main = self.main;
+        for entry in extra.iter().filter(|e| e.section == main) {
+            self.push_extra(entry);
+        }
+        if let Some(restart) = &service.restart {
+            self.push_in("Service", "Restart", restart);
+        }
+        if let Some(timeout) = &service.timeout_start_sec {
+            self.push_in("Service", "TimeoutStartSec", timeout);
+        }
+        for wanted_by in &install.wanted_by {
+            self.push_in("Install", "WantedBy", wanted_by);
+        }
+        for entry in extra.iter().filter(|e| e.section != main) {
+            self.push_extra(entry);
+        }
+    }
+
+    fn finish(self) -> Result<String, String> {
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+        Ok(self
+            .sections
+            .iter()
+            .filter(|(name, entries)| name == self.main || !entries.is_empty())
+            .map(|(name, entries)| {
+                let mut text = format!("[{}]\n", name);
+                for (key, value, _) in entries {
+                    text.push_str(&format!("{}={}\n", key, value));
+                }
+                text
+            })
+            .collect::<Vec<_>>()
+            .join("\n"))
+    }
+}
+
+/// Comprueba que una asignación se puede escribir en una sola línea y sin
+/// abrir otra sección
+pub fn check_entry(section: &str, key: &str, value: Option<&str>) -> Result<(), String> {
+    let invalid = |s: &str| s.is_empty() || s.contains(['\n', '\r']);
+    if invalid(section) || section.contains(['[', ']', '=']) {
+        return Err(format!("Invalid section: {}", section.escape_default()));
+    }
+    if invalid(key) || key.contains(['=', '[', ']', '#', ';']) || key.trim() != key {
+        return Err(format!("Invalid key: {}", key.escape_default()));
+    }
+    if value.is_some_and(|v| v.contains(['\n', '\r'])) {
+        return Err(format!(
+            "Invalid value for {}: line breaks are not allowed",
+            key
+        ));
+    }
+    // systemd une la línea siguiente a la que acaba en `\`
+    if value.is_some_and(|v| v.trim_end().ends_with('\\')) {
+        return Err(format!(
+            "Invalid value for {}: a trailing backslash would continue on the next line",
+            key
+        ));
+    }
+    Ok(())
+}
+
+/// Entrecomilla una palabra si contiene espacios o comillas
+fn quote_word(word: &str) -> String {
+    if word.contains(|c: char| c.is_whitespace() || c == '"' || c == '\'' || c == '\\') {
+        format!("\"{}\"", word.replace('\\', "\\\\").replace('"', "\\\""))
+    } else {
+        word.to_string()
+    }
+}
+
+/// Lee valores de un `UnitFile` recordando qué claves ya se han consumido,
+/// para que el resto acabe en `extra`
+struct Reader<'a> {
+    unit: &'a UnitFile,
+    section: &'static str,
+    used: HashSet<(&'static str, &'static str)>,
+    /// Palabras de claves consumidas que no encajan en su campo tipado
+    leftover: Vec<ExtraEntry>,
+}
+
+impl<'a> Reader<'a> {
+    fn new(unit: &'a UnitFile, section: &'static str) -> Self {
+        Self {
+            unit,
+            section,
+            used: HashSet::new(),
+            leftover: Vec::new(),
+        }
+    }
+
+    /// Lee una clave de un solo valor. Como en systemd gana la última
+    /// asignación; las anteriores se guardan en `extra`.
+    fn one_in(&mut self, section: &'static str, key: &'static str) -> Option<String> {
+        let mut values = self.all_in(section, key);
+        let value = values.pop();
+        for value in values {
+            self.keep(section, key, value);
+        }
+        value
+    }
+
+    fn all_in(&mut self, section: &'static str, key: &'static str) -> Vec<String> {
+        self.used.insert((section, key));
+        self.unit
+            .get_all(section, key)
+            .into_iter()
+            .map(String::from)
+            .collect()
+    }
+
+    fn words_in(&mut self, section: &'static str, key: &'static str) -> Vec<String> {
+        self.all_in(section, key)
+            .iter()
+            .flat_map(|v| split_words(v))
+            .collect()
+    }
+
+    fn one(&mut self, key: &'static str) -> Option<String> {
+        self.one_in(self.section, key)
+    }
+
+    fn all(&mut self, key: &'static str) -> Vec<String> {
+        self.all_in(self.section, key)
+    }
+
+    fn words(&mut self, key: &'static str) -> Vec<String> {
+        self.words_in(self.section, key)
+    }
+
+    /// Lee un booleano; si no se puede interpretar se guarda en `extra`
+    fn boolean(&mut self, key: &'static str) -> Option<bool> {
+        let value = self.one(key)?;
+        let parsed = parse_bool(&value);
+        if parsed.is_none() {
+            self.keep(self.section, key, value);
+        }
+        parsed
+    }
+
+    /// Guarda un valor de una clave consumida que no cabe en su campo
+    fn keep(&mut self, section: &str, key: &str, value: String) {
+        self.leftover.push(ExtraEntry {
+            section: section.to_string(),
+            key: key.to_string(),
+            value,
+        });
+    }
+
+    /// Lee claves como `Environment=` o `Label=` con pares `CLAVE=VALOR`.
+    ///
+    /// Las palabras sin `=` (`Environment=FOO`, que hereda la variable) no
+    /// son pares y se guardan en `extra` para no perderlas al volver a
+    /// generar el archivo.
+    fn pairs(&mut self, key: &'static str) -> BTreeMap<String, String> {
+        let mut pairs = BTreeMap::new();
+        for word in self.words(key) {
+            match word.split_once('=') {
+                Some((k, v)) => {
+                    pairs.insert(k.to_string(), v.to_string());
+                }
+                None => self.keep(self.section, key, quote_word(&word)),
+            }
+        }
+        pairs
+    }
+
+    fn unit(&mut self) -> UnitOptions {
+        UnitOptions {
+            description: self.one_in("Unit", "Description"),
+            requires: self.words_in("Unit", "Requires"),
+            wants: self.words_in("Unit", "Wants"),
+            after: self.words_in("Unit", "After"),
+            before: self.words_in("Unit", "Before"),
+        }
+    }
+
+    fn service(&mut self) -> ServiceOptions {
+        ServiceOptions {
+            restart: self.one_in("Service", "Restart"),
+            timeout_start_sec: self.one_in("Service", "TimeoutStartSec"),
+        }
+    }
+
+    fn install(&mut self) -> InstallOptions {
+        InstallOptions {
+            wanted_by: self.words_in("Install", "WantedBy"),
+        }
+    }
+
+    /// Asignaciones que no se han leído en ningún campo tipado
+    fn rest(&self) -> Vec<ExtraEntry> {
+        self.unit
+            .entries()
+            .into_iter()
+            .filter_map(|e| {
+                let section = e.section?;
+                (!self.used.contains(&(section, e.key))).then(|| ExtraEntry {
+                    section: section.to_string(),
+                    key: e.key.to_string(),
+                    value: e.value.to_string(),
+                })
+            })
+            .chain(self.leftover.iter().cloned())
+            .collect()
+    }
+}
+
+pub fn parse_bool(value: &str) -> Option<bool> {
+    match value.to_lowercase().as_str() {
+        "true" | "yes" | "on" | "1" => Some(true),
+        "false" | "no" | "off" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+/// Divide un valor en palabras separadas por espacios respetando comillas,
+/// como hace systemd con `Environment=`
+pub fn split_words(value: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut quote: Option<char> = None;
+    let mut in_word = false;
+    let mut chars = value.chars();
+
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), '\\') => {
+                if let Some(next) = chars.next() {
+                    current.push(next);
+                }
+            }
+            (Some(_), c) => current.push(c),
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                in_word = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut current));
+                    in_word = false;
+                }
+            }
+            (None, c) => {
+                current.push(c);
+                in_word = true;
+            }
+        }
+    }
+    if in_word {
+        words.push(current);
+    }
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONTAINER: &str = "[Unit]\nDescription=Web\nAfter=db.service network-online.target\n\n[Container]\nImage=docker.io/nginx:latest\nPublishPort=8080:80\nPublishPort=8443:443\nVolume=data.volume:/data\nEnvironment=TZ=Europe/Madrid \"GREETING=hola mundo\"\nEnvironment=DEBUG=1\nLabel=app=web\nNetwork=web.network\nPod=app.pod\nReadOnly=yes\nTimezone=local\n\n[Service]\nRestart=always\n\n[Install]\nWantedBy=default.target\n";
+
+    #[test]
+    fn test_container_spec() {
+        let spec = QuadletSpec::from_unit(QuadletType::Container, &UnitFile::parse(CONTAINER));
+        let QuadletSpec::Container(container) = spec else {
+            panic!("expected a container spec");
+        };
+        assert_eq!(container.image.as_deref(), Some("docker.io/nginx:latest"));
+        assert_eq!(container.ports, vec!["8080:80", "8443:443"]);
+        assert_eq!(container.volumes, vec!["data.volume:/data"]);
+        assert_eq!(
+            container.env.get("GREETING").map(String::as_str),
+            Some("hola mundo")
+        );
+        assert_eq!(container.env.get("DEBUG").map(String::as_str), Some("1"));
+        assert_eq!(container.labels.get("app").map(String::as_str), Some("web"));
+        assert_eq!(container.networks, vec!["web.network"]);
+        assert_eq!(container.pod.as_deref(), Some("app.pod"));
+        assert_eq!(container.read_only, Some(true));
+        assert_eq!(container.unit.description.as_deref(), Some("Web"));
+        assert_eq!(
+            container.unit.after,
+            vec!["db.service", "network-online.target"]
+        );
+        assert_eq!(container.service.restart.as_deref(), Some("always"));
+        assert_eq!(container.install.wanted_by, vec!["default.target"]);
+        assert_eq!(
+            container.extra,
+            vec![ExtraEntry {
+                section: "Container".to_string(),
+                key: "Timezone".to_string(),
+                value: "local".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_pairs_without_value_are_kept() {
+        let unit = UnitFile::parse("[Container]\nImage=nginx\nEnvironment=FOO BAR=1\n");
+        let spec = QuadletSpec::from_unit(QuadletType::Container, &unit);
+        let QuadletSpec::Container(container) = &spec else {
+            panic!("expected a container spec");
+        };
+        assert_eq!(container.env.get("BAR").map(String::as_str), Some("1"));
+        assert_eq!(
+            container.extra,
+            vec![ExtraEntry {
+                section: "Container".to_string(),
+                key: "Environment".to_string(),
+                value: "FOO".to_string(),
+            }]
+        );
+        let rendered = spec.render().unwrap();
+        assert!(rendered.contains("Environment=BAR=1\n"), "{rendered}");
+        assert!(rendered.contains("Environment=FOO\n"), "{rendered}");
+    }
+
+    #[test]
+    fn test_unparsed_values_are_kept() {
+        let content = "[Unit]\nDescription=Old\nDescription=Web\n\n[Container]\nImage=nginx:1\nImage=nginx:2\nReadOnly=maybe\n";
+        let spec = QuadletSpec::from_unit(QuadletType::Container, &UnitFile::parse(content));
+        let QuadletSpec::Container(container) = &spec else {
+            panic!("expected a container spec");
+        };
+        assert_eq!(container.image.as_deref(), Some("nginx:2"));
+        assert_eq!(container.unit.description.as_deref(), Some("Web"));
+        assert_eq!(container.read_only, None);
+        let extra: Vec<(&str, &str)> = container
+            .extra
+            .iter()
+            .map(|e| (e.key.as_str(), e.value.as_str()))
+            .collect();
+        assert_eq!(
+            extra,
+            vec![
+                ("Image", "nginx:1"),
+                ("ReadOnly", "maybe"),
+                ("Description", "Old")
+            ]
+        );
+
+        // Al volver a generar el archivo siguen ganando los mismos valores
+        let rendered = spec.render().unwrap();
+        assert!(
+            rendered.contains("Image=nginx:1\nImage=nginx:2\n"),
+            "{rendered}"
+        );
+        assert!(
+            rendered.contains("Description=Old\nDescription=Web\n"),
+            "{rendered}"
+        );
+        assert!(rendered.contains("ReadOnly=maybe\n"), "{rendered}");
+        let reparsed = QuadletSpec::from_unit(QuadletType::Container, &UnitFile::parse(&rendered));
+        assert_eq!(reparsed, spec);
+    }
+
+    #[test]
+    fn test_render_rejects_line_breaks() {
+        let spec: QuadletSpec = serde_json::from_value(serde_json::json!({
+            "kind": "container",
+            "image": "nginx\n[Service]\nExecStartPre=/bin/sh -c id",
+        }))
+        .unwrap();
+        assert!(spec.render().unwrap_err().contains("line breaks"));
+
+        for (section, key) in [("X-Custom]\n[Service", "Owner"), ("X-Custom", "Exec=x")] {
+            let spec: QuadletSpec = serde_json::from_value(serde_json::json!({
+                "kind": "container",
+                "image": "nginx",
+                "extra": [{"section": section, "key": key, "value": "ops"}],
+            }))
+            .unwrap();
+            assert!(spec.render().unwrap_err().starts_with("Invalid"));
+        }
+    }
+
+    #[test]
+    fn test_render_rejects_trailing_backslash() {
+        let spec: QuadletSpec = serde_json::from_value(serde_json::json!({
+            "kind": "container",
+            "image": "nginx",
+            "exec": "foo \\",
+            "user": "web",
+        }))
+        .unwrap();
+        assert!(spec.render().unwrap_err().contains("trailing backslash"));
+
+        // Las barras en medio del valor o entre comillas no continúan la línea
+        let spec: QuadletSpec = serde_json::from_value(serde_json::json!({
+            "kind": "container",
+            "image": "nginx",
+            "exec": "printf a\\tb",
+            "env": {"DIR": "C:\\"},
+            "user": "web",
+        }))
+        .unwrap();
+        let content = spec.render().unwrap();
+        let parsed = QuadletSpec::from_unit(QuadletType::Container, &UnitFile::parse(&content));
+        assert_eq!(parsed, spec, "{content}");
+    }
+
+    #[test]
+    fn test_other_specs() {
+        let network = QuadletSpec::from_unit(
+            QuadletType::Network,
+            &UnitFile::parse("[Network]\nSubnet=10.89.0.0/24\nInternal=true\n"),
+        );
+        assert!(matches!(
+            network,
+            QuadletSpec::Network(NetworkSpec { ref subnets, internal: Some(true), .. }) if subnets == &["10.89.0.0/24"]
+        ));
+
+        let volume = QuadletSpec::from_unit(
+            QuadletType::Volume,
+            &UnitFile::parse("[Volume]\nType=nfs\nDevice=server:/export\n"),
+        );
+        let QuadletSpec::Volume(volume) = volume else {
+            panic!("expected a volume spec");
+        };
+        assert_eq!(volume.fs_type.as_deref(), Some("nfs"));
+
+        let kube = QuadletSpec::from_unit(
+            QuadletType::Kube,
+            &UnitFile::parse("[Kube]\nYaml=app.yml\n"),
+        );
+        assert_eq!(kube.kind(), QuadletType::Kube);
+    }
+
+    #[test]
+    fn test_spec_json() {
+        let spec = QuadletSpec::from_unit(QuadletType::Container, &UnitFile::parse(CONTAINER));
+        let json = serde_json::to_value(&spec).unwrap();
+        assert_eq!(json["kind"], "container");
+        assert_eq!(json["image"], "docker.io/nginx:latest");
+
+        let parsed: QuadletSpec =
+            serde_json::from_str(r#"{"kind": "pod", "pod_name": "app", "ports": ["80:80"]}"#)
+                .unwrap();
+        let QuadletSpec::Pod(pod) = parsed else {
+            panic!("expected a pod spec");
+        };
+        assert_eq!(pod.pod_name.as_deref(), Some("app"));
+        assert_eq!(pod.ports, vec!["80:80"]);
+    }
+
+    #[test]
+    fn test_render_container() {
+        let spec: QuadletSpec = serde_json::from_str(
+            r#"{
+                "kind": "container",
+                "image": "docker.io/nginx:latest",
+                "ports": ["8080:80"],
+                "env": {"TZ": "Europe/Madrid", "GREETING": "hola mundo"},
+                "networks": ["web.network"],
+                "unit": {"description": "Web"},
+                "install": {"wanted_by": ["default.target"]},
+                "extra": [{"section": "X-Custom", "key": "Owner", "value": "ops"}]
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            spec.render().unwrap(),
+            "[Unit]\nDescription=Web\n\n[Container]\nImage=docker.io/nginx:latest\nPublishPort=8080:80\nEnvironment=\"GREETING=hola mundo\"\nEnvironment=TZ=Europe/Madrid\nNetwork=web.network\n\n[Install]\nWantedBy=default.target\n\n[X-Custom]\nOwner=ops\n"
+        );
+    }
+
+    #[test]
+    fn test_render_is_canonical() {
+        let spec = QuadletSpec::from_unit(QuadletType::Container, &UnitFile::parse(CONTAINER));
+        let rendered = spec.render().unwrap();
+        let reparsed = QuadletSpec::from_unit(QuadletType::Container, &UnitFile::parse(&rendered));
+        assert_eq!(reparsed, spec);
+        assert_eq!(reparsed.render().unwrap(), rendered);
+    }
+
+    #[test]
+    fn test_render_empty_spec() {
+        let spec = QuadletSpec::Network(NetworkSpec::default());
+        assert_eq!(spec.render().unwrap(), "[Network]\n");
+    }
+
+    #[test]
+    fn test_quote_word() {
+        assert_eq!(quote_word("A=1"), "A=1");
+        assert_eq!(quote_word("A=x y"), "\"A=x y\"");
+        assert_eq!(
+            split_words(&quote_word("A=say \"hi\"")),
+            vec!["A=say \"hi\""]
+        );
+    }
+
+    #[test]
+    fn test_split_words() {
+        assert_eq!(split_words("A=1  B=2"), vec!["A=1", "B=2"]);
+        assert_eq!(split_words("\"A=x y\" 'B=z'"), vec!["A=x y", "B=z"]);
+        assert_eq!(
+            split_words("A=\"quoted \\\" inner\""),
+            vec!["A=quoted \" inner"]
+        );
+        assert!(split_words("  ").is_empty());
+    }
+}
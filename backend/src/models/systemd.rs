@@ -0,0 +1,214 @@
+use serde::{Deserialize, Serialize};
+
+use crate::constants::{DEFAULT_LOG_LINES, MAX_LOG_LINES};
+
+/// Prioridades de syslog que admite `journalctl --priority`, de la más a
+/// la menos grave
+const PRIORITIES: [&str; 8] = [
+    "emerg", "alert", "crit", "err", "warning", "notice", "info", "debug",
+];
+
+/// Propiedades de `systemctl show` que se consultan para conocer el estado
+/// de una unidad
+pub const UNIT_STATE_PROPERTIES: &str =
+    "LoadState,ActiveState,SubState,UnitFileState,StateChangeTimestamp,MainPID,NRestarts";
+
+/// Estado de una unidad systemd
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UnitState {
+    /// Nombre de la unidad, p. ej. `web.service`
+    pub unit: String,
+    /// `loaded`, `not-found`...
+    pub load_state: String,
+    /// `active`, `inactive`, `failed`...
+    pub active_state: String,
+    /// `running`, `dead`, `exited`...
+    pub sub_state: String,
+    /// `enabled`, `disabled`, `generated`... (vacío si no tiene archivo)
+    pub unit_file_state: String,
+    /// Momento del último cambio de estado, tal y como lo muestra systemd
+    /// (vacío si no ha cambiado nunca)
+    #[serde(default)]
+    pub since: String,
+    /// PID del proceso principal, 0 si no hay ninguno
+    #[serde(default)]
+    pub main_pid: u32,
+    /// Veces que systemd ha reiniciado la unidad automáticamente
+    #[serde(default)]
+    pub restart_count: u32,
+    /// Indica si la unidad existe; en una unidad de un Quadlet, `false`
+    /// significa que el generador no la ha creado (p. ej. por un error en
+    /// el archivo)
+    #[serde(default)]
+    pub generated: bool,
+}
+
+impl UnitState {
+    /// Interpreta la salida de `systemctl show --property=...`
+    pub fn from_show(unit: &str, output: &str) -> Self {
+        let mut state = Self {
+            unit: unit.to_string(),
+            ..Default::default()
+        };
+        for line in output.lines() {
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let value = value.trim().to_string();
+            match key.trim() {
+                "LoadState" => state.load_state = value,
+                "ActiveState" => state.active_state = value,
+                "SubState" => state.sub_state = value,
+                "UnitFileState" => state.unit_file_state = value,
+                "StateChangeTimestamp" => state.since = value,
+                "MainPID" => state.main_pid = value.parse().unwrap_or_default(),
+                "NRestarts" => state.restart_count = value.parse().unwrap_or_default(),
+                _ => {}
+            }
+        }
+        state.generated = !state.load_state.is_empty() && state.load_state != "not-found";
+        state
+    }
+
+    /// Indica si la unidad está en marcha o arrancando
+    pub fn is_active(&self) -> bool {
+        matches!(self.active_state.as_str(), "active" | "activating" | "reloading")
+    }
+}
+
+/// Filtros para leer el journal de una unidad
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JournalQuery {
+    /// Desde cuándo, en cualquier formato que entienda `journalctl`
+    /// (`2026-10-17 09:00`, `-1h`, `today`...)
+    pub since: Option<String>,
+    /// Hasta cuándo, en el mismo formato que `since`
+    pub until: Option<String>,
+    /// Últimas líneas que se devuelven (por defecto `DEFAULT_LOG_LINES`)
+    pub lines: Option<usize>,
+    /// Prioridad máxima (`err`, `3`...) o rango (`err..warning`)
+    pub priority: Option<String>,
+}
+
+impl JournalQuery {
+    /// Consulta de las últimas `lines` líneas
+    pub fn last(lines: usize) -> Self {
+        Self {
+            lines: Some(lines),
+            ..Default::default()
+        }
+    }
+
+    /// Líneas que se piden, limitadas a `MAX_LOG_LINES`
+    pub fn lines_or_default(&self) -> usize {
+        self.lines.unwrap_or(DEFAULT_LOG_LINES).min(MAX_LOG_LINES)
+    }
+
+    /// Comprueba los filtros antes de pasárselos a `journalctl`
+    pub fn validate(&self) -> Result<(), String> {
+        for (name, value) in [("since", &self.since), ("until", &self.until)] {
+            if let Some(value) = value
+                && (value.trim().is_empty() || value.chars().any(char::is_control))
+            {
+                return Err(format!("Invalid {}: {:?}", name, value));
+            }
+        }
+        if let Some(priority) = &self.priority {
+            let valid = priority
+                .split("..")
+                .all(|p| PRIORITIES.contains(&p) || p.parse::<u8>().is_ok_and(|n| n <= 7));
+            if !valid || priority.matches("..").count() > 1 {
+                return Err(format!("Invalid priority: {}", priority));
+            }
+        }
+        Ok(())
+    }
+
+    /// Argumentos de `journalctl` para estos filtros
+    pub fn args(&self) -> Vec<String> {
+        let mut args = vec![format!("--lines={}", self.lines_or_default())];
+        if let Some(since) = &self.since {
+            args.push(format!("--since={}", since));
+        }
+        if let Some(until) = &self.until {
+            args.push(format!("--until={}", until));
+        }
+        if let Some(priority) = &self.priority {
+            args.push(format!("--priority={}", priority));
+        }
+        args
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unit_state_from_show() {
+        let state = UnitState::from_show(
+            "web.service",
+            "LoadState=loaded\nActiveState=active\nSubState=running\nUnitFileState=generated\n\
+             StateChangeTimestamp=Sat 2026-10-17 09:12:03 CEST\nMainPID=4242\nNRestarts=3\n",
+        );
+        assert_eq!(state.unit, "web.service");
+        assert_eq!(state.since, "Sat 2026-10-17 09:12:03 CEST");
+        assert_eq!(state.main_pid, 4242);
+        assert_eq!(state.restart_count, 3);
+        assert!(state.generated);
+        assert_eq!(state.sub_state, "running");
+        assert_eq!(state.unit_file_state, "generated");
+        assert!(state.is_active());
+
+        let state = UnitState::from_show(
+            "gone.service",
+            "LoadState=not-found\nActiveState=inactive\nSubState=dead\nUnitFileState=\n\
+             StateChangeTimestamp=\nMainPID=0\nNRestarts=0\n",
+        );
+        assert_eq!(state.load_state, "not-found");
+        assert!(!state.generated);
+        assert_eq!(state.main_pid, 0);
+        assert!(!state.is_active());
+        assert_eq!(state.unit_file_state, "");
+    }
+
+    #[test]
+    fn test_journal_query() {
+        let query = JournalQuery::default();
+        assert_eq!(query.args(), ["--lines=100"]);
+        assert!(query.validate().is_ok());
+
+        let query = JournalQuery {
+            since: Some("-1h".to_string()),
+            until: Some("2026-10-17 10:00".to_string()),
+            lines: Some(1_000_000),
+            priority: Some("err..warning".to_string()),
+        };
+        assert!(query.validate().is_ok());
+        assert_eq!(
+            query.args(),
+            [
+                "--lines=10000",
+                "--since=-1h",
+                "--until=2026-10-17 10:00",
+                "--priority=err..warning"
+            ]
+        );
+
+        let priority = |p: &str| JournalQuery {
+            priority: Some(p.to_string()),
+            ..Default::default()
+        };
+        for valid in ["3", "0..7", "debug"] {
+            assert!(priority(valid).validate().is_ok(), "{valid}");
+        }
+        for invalid in ["8", "error", "err..", "0..1..2"] {
+            assert!(priority(invalid).validate().is_err(), "{invalid}");
+        }
+        let query = JournalQuery {
+            since: Some("today\n".to_string()),
+            ..Default::default()
+        };
+        assert!(query.validate().is_err());
+    }
+}
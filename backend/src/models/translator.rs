@@ -0,0 +1,681 @@
+use serde::Serialize;
+use std::collections::HashSet;
+
+use super::spec::{parse_bool, split_words};
+use super::{EffectiveConfig, Quadlet, QuadletType, UnitFile};
+
+/// Ruta de `podman` en las unidades generadas, la misma que usa el generador
+const PODMAN: &str = "/usr/bin/podman";
+
+/// Unidad de systemd equivalente a un Quadlet, calculada sin Podman
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Translation {
+    /// Nombre de la unidad, p. ej. `web.service`
+    pub unit: String,
+    /// Contenido de la unidad
+    pub content: String,
+    /// Orden de `podman` que crea el recurso (`podman run ...`,
+    /// `podman volume create ...`)
+    pub command: Vec<String>,
+    /// Claves de la sección del Quadlet que el traductor no conoce y no se
+    /// han tenido en cuenta, como `Container.Foo`
+    pub ignored: Vec<String>,
+}
+
+/// Traduce un Quadlet (con sus drop-ins) a la unidad de systemd que generaría
+/// Podman.
+///
+/// Cubre las claves habituales de `.container`, `.volume`, `.network` y
+/// `.pod`. Los recursos a los que se hace referencia (`Volume=data.volume:...`,
+/// `Network=web.network`, `Pod=app.pod`) se nombran como `systemd-{nombre}`,
+/// que es el nombre por defecto; no se leen sus archivos para ver si lo
+/// cambian.
+pub fn translate(quadlet: &Quadlet) -> Result<Translation, String> {
+    let effective = EffectiveConfig::merge(quadlet, &quadlet.dropins);
+    let file = UnitFile::parse(&effective.content);
+    let section = quadlet.kind.section();
+    let mut keys = Keys::new(&file, section);
+    let mut unit = Unit::new(&file, section, &quadlet.path.to_string_lossy());
+    // Afecta solo al nombre de la unidad
+    keys.last("ServiceName");
+
+    let command = match quadlet.kind {
+        QuadletType::Container => container(&mut keys, &mut unit)?,
+        QuadletType::Volume => volume(&mut keys, &mut unit, &quadlet.name),
+        QuadletType::Network => network(&mut keys, &mut unit, &quadlet.name),
+        QuadletType::Pod => pod(&mut keys, &mut unit, &quadlet.name),
+        QuadletType::Kube | QuadletType::Image => {
+            return Err(format!(
+                "{} quadlets are not supported by the native translator",
+                quadlet.kind.section()
+            ));
+        }
+    };
+
+    Ok(Translation {
+        unit: quadlet.unit_name(),
+        content: unit.render(),
+        command,
+        ignored: keys.ignored(section),
+    })
+}
+
+/// `.container`: `podman run` en una unidad `Type=notify`
+fn container(keys: &mut Keys, unit: &mut Unit) -> Result<Vec<String>, String> {
+    let image = keys
+        .last("Image")
+        .ok_or("No Image key specified")?
+        .to_string();
+    let name = keys
+        .last("ContainerName")
+        .unwrap_or("systemd-%N")
+        .to_string();
+    let mut args = command(&["run", "--name", &name]);
+    args.extend(
+        [
+            "--cidfile=%t/%N.cid",
+            "--replace",
+            "--rm",
+            "--cgroups=split",
+            "--sdnotify=conmon",
+            "-d",
+        ]
+        .map(String::from),
+    );
+
+    for port in keys.all("PublishPort") {
+        args.extend(["--publish".to_string(), port]);
+    }
+    for volume in keys.all("Volume") {
+        let volume = resource(unit, &volume, QuadletType::Volume);
+        args.extend(["-v".to_string(), volume]);
+    }
+    for mount in keys.all("Mount") {
+        args.extend(["--mount".to_string(), mount]);
+    }
+    for network in keys.all("Network") {
+        let network = resource(unit, &network, QuadletType::Network);
+        args.extend(["--network".to_string(), network]);
+    }
+    for alias in keys.all("NetworkAlias") {
+        args.extend(["--network-alias".to_string(), alias]);
+    }
+    if let Some(pod) = keys.last("Pod").map(String::from) {
+        let pod = resource(unit, &pod, QuadletType::Pod);
+        args.extend(["--pod".to_string(), pod]);
+    }
+    for env in keys.words("Environment") {
+        args.extend(["--env".to_string(), env]);
+    }
+    for file in keys.all("EnvironmentFile") {
+        args.extend(["--env-file".to_string(), file]);
+    }
+    for label in keys.words("Label") {
+        args.extend(["--label".to_string(), label]);
+    }
+    let user = match (keys.last("User"), keys.last("Group")) {
+        (Some(user), Some(group)) => Some(format!("{}:{}", user, group)),
+        (Some(user), None) => Some(user.to_string()),
+        (None, _) => None,
+    };
+    if let Some(user) = user {
+        args.extend(["--user".to_string(), user]);
+    }
+    for (key, flag) in [
+        ("HostName", "--hostname"),
+        ("WorkingDir", "-w"),
+        ("Entrypoint", "--entrypoint"),
+        ("HealthCmd", "--health-cmd"),
+        ("Pull", "--pull"),
+        ("Timezone", "--tz"),
+    ] {
+        if let Some(value) = keys.last(key) {
+            args.extend([flag.to_string(), value.to_string()]);
+        }
+    }
+    for secret in keys.all("Secret") {
+        args.extend(["--secret".to_string(), secret]);
+    }
+    for device in keys.all("AddDevice") {
+        args.extend(["--device".to_string(), device]);
+    }
+    for capability in keys.words("AddCapability") {
+        args.extend(["--cap-add".to_string(), capability.to_lowercase()]);
+    }
+    for capability in keys.words("DropCapability") {
+        args.extend(["--cap-drop".to_string(), capability.to_lowercase()]);
+    }
+    if keys.flag("ReadOnly") {
+        args.push("--read-only".to_string());
+    }
+    if keys.flag("NoNewPrivileges") {
+        args.push("--security-opt=no-new-privileges".to_string());
+    }
+    if let Some(policy) = keys.last("AutoUpdate") {
+        args.extend([
+            "--label".to_string(),
+            format!("io.containers.autoupdate={}", policy),
+        ]);
+    }
+    args.extend(keys.words("PodmanArgs"));
+    args.push(image);
+    args.extend(keys.words("Exec"));
+
+    unit.service.extend(
+        [
+            ("Environment", "PODMAN_SYSTEMD_UNIT=%n"),
+            ("KillMode", "mixed"),
+            (
+                "ExecStop",
+                "/usr/bin/podman rm -v -f -i --cidfile=%t/%N.cid",
+            ),
+            (
+                "ExecStopPost",
+                "-/usr/bin/podman rm -v -f -i --cidfile=%t/%N.cid",
+            ),
+            ("Delegate", "yes"),
+            ("Type", "notify"),
+            ("NotifyAccess", "all"),
+            ("SyslogIdentifier", "%N"),
+        ]
+        .map(|(k, v)| (k.to_string(), v.to_string())),
+    );
+    unit.service
+        .push(("ExecStart".to_string(), exec_line(&args)));
+    Ok(args)
+}
+
+/// `.volume`: `podman volume create` en una unidad `oneshot`
+fn volume(keys: &mut Keys, unit: &mut Unit, name: &str) -> Vec<String> {
+    let volume_name = keys
+        .last("VolumeName")
+        .map(String::from)
+        .unwrap_or_else(|| format!("systemd-{}", name));
+    let mut args = command(&["volume", "create", "--ignore"]);
+    if let Some(driver) = keys.last("Driver") {
+        args.extend(["--driver".to_string(), driver.to_string()]);
+    }
+    let mut options = Vec::new();
+    for (key, option) in [
+        ("Type", "type"),
+        ("Device", "device"),
+        ("Options", "o"),
+        ("User", "uid"),
+        ("Group", "gid"),
+        ("Image", "image"),
+    ] {
+        if let Some(value) = keys.last(key) {
+            options.push(format!("{}={}", option, value));
+        }
+    }
+    if keys.last("Copy").and_then(parse_bool) == Some(false) {
+        options.push("nocopy".to_string());
+    }
+    for option in options {
+        args.extend(["--opt".to_string(), option]);
+    }
+    for label in keys.words("Label") {
+        args.extend(["--label".to_string(), label]);
+    }
+    args.extend(keys.words("PodmanArgs"));
+    args.push(volume_name);
+    oneshot(unit, &args);
+    args
+}
+
+/// `.network`: `podman network create` en una unidad `oneshot`
+fn network(keys: &mut Keys, unit: &mut Unit, name: &str) -> Vec<String> {
+    let network_name = keys
+        .last("NetworkName")
+        .map(String::from)
+        .unwrap_or_else(|| format!("systemd-{}", name));
+    let mut args = command(&["network", "create", "--ignore"]);
+    if let Some(driver) = keys.last("Driver") {
+        args.extend(["--driver".to_string(), driver.to_string()]);
+    }
+    for (key, flag) in [
+        ("Subnet", "--subnet"),
+        ("Gateway", "--gateway"),
+        ("IPRange", "--ip-range"),
+        ("DNS", "--dns"),
+        ("Options", "--opt"),
+    ] {
+        for value in keys.all(key) {
+            args.extend([flag.to_string(), value]);
+        }
+    }
+    for (key, flag) in [
+        ("Internal", "--internal"),
+        ("IPv6", "--ipv6"),
+        ("DisableDNS", "--disable-dns"),
+    ] {
+        if keys.flag(key) {
+            args.push(flag.to_string());
+        }
+    }
+    for label in keys.words("Label") {
+        args.extend(["--label".to_string(), label]);
+    }
+    args.extend(keys.words("PodmanArgs"));
+    args.push(network_name);
+    oneshot(unit, &args);
+    args
+}
+
+/// `.pod`: `podman pod create` al arrancar y `podman pod start` como proceso
+/// principal
+fn pod(keys: &mut Keys, unit: &mut Unit, name: &str) -> Vec<String> {
+    let pod_name = keys
+        .last("PodName")
+        .map(String::from)
+        .unwrap_or_else(|| format!("systemd-{}", name));
+    let mut args = command(&[
+        "pod",
+        "create",
+        "--infra-conmon-pidfile=%t/%N.pid",
+        "--pod-id-file=%t/%N.pod-id",
+        "--exit-policy=stop",
+        "--replace",
+    ]);
+    for port in keys.all("PublishPort") {
+        args.extend(["--publish".to_string(), port]);
+    }
+    for network in keys.all("Network") {
+        let network = resource(unit, &network, QuadletType::Network);
+        args.extend(["--network".to_string(), network]);
+    }
+    for alias in keys.all("NetworkAlias") {
+        args.extend(["--network-alias".to_string(), alias]);
+    }
+    for volume in keys.all("Volume") {
+        let volume = resource(unit, &volume, QuadletType::Volume);
+        args.extend(["--volume".to_string(), volume]);
+    }
+    if let Some(hostname) = keys.last("HostName") {
+        args.extend(["--hostname".to_string(), hostname.to_string()]);
+    }
+    for label in keys.words("Label") {
+        args.extend(["--label".to_string(), label]);
+    }
+    args.extend(keys.words("PodmanArgs"));
+    args.extend(["--name".to_string(), pod_name]);
+
+    unit.service.extend(
+        [
+            ("Environment", "PODMAN_SYSTEMD_UNIT=%n"),
+            ("Type", "forking"),
+            ("Restart", "on-failure"),
+            ("PIDFile", "%t/%N.pid"),
+            ("SyslogIdentifier", "%N"),
+            ("ExecStartPre", &exec_line(&args)),
+            (
+                "ExecStart",
+                "/usr/bin/podman pod start --pod-id-file=%t/%N.pod-id",
+            ),
+            (
+                "ExecStop",
+                "/usr/bin/podman pod stop --pod-id-file=%t/%N.pod-id --ignore --time=10",
+            ),
+            (
+                "ExecStopPost",
+                "/usr/bin/podman pod rm --pod-id-file=%t/%N.pod-id --ignore --force",
+            ),
+        ]
+        .map(|(k, v)| (k.to_string(), v.to_string())),
+    );
+    args
+}
+
+/// Servicio que crea un recurso y se queda activo
+fn oneshot(unit: &mut Unit, args: &[String]) {
+    unit.service.extend(
+        [
+            ("Type", "oneshot"),
+            ("RemainAfterExit", "yes"),
+            ("SyslogIdentifier", "%N"),
+            ("ExecStart", &exec_line(args)),
+        ]
+        .map(|(k, v)| (k.to_string(), v.to_string())),
+    );
+}
+
+/// `podman` seguido de los argumentos
+fn command(args: &[&str]) -> Vec<String> {
+    std::iter::once(PODMAN)
+        .chain(args.iter().copied())
+        .map(String::from)
+        .collect()
+}
+
+/// Sustituye la referencia a otro Quadlet (`data.volume:/data`) por el
+/// nombre del recurso (`systemd-data:/data`) y añade la dependencia de su
+/// unidad
+fn resource(unit: &mut Unit, value: &str, kind: QuadletType) -> String {
+    let (source, rest) = match value.split_once(':') {
+        Some((source, rest)) => (source, Some(rest)),
+        None => (value, None),
+    };
+    let Some(name) = source.strip_suffix(kind.extension()) else {
+        return value.to_string();
+    };
+    let service = format!("{}-{}.service", name, kind.as_str());
+    // Los contenedores de un pod se paran con él
+    let requirement = if kind == QuadletType::Pod {
+        "BindsTo"
+    } else {
+        "Requires"
+    };
+    unit.dependencies
+        .push((requirement.to_string(), service.clone()));
+    unit.dependencies.push(("After".to_string(), service));
+    let resource = format!("systemd-{}", name);
+    match rest {
+        Some(rest) => format!("{}:{}", resource, rest),
+        None => resource,
+    }
+}
+
+/// Línea de `ExecStart=`, con comillas en los argumentos que las necesitan
+fn exec_line(args: &[String]) -> String {
+    args.iter()
+        .map(|arg| {
+            if !arg.is_empty() && !arg.contains(|c: char| c.is_whitespace() || "\"'\\".contains(c))
+            {
+                return arg.clone();
+            }
+            format!("\"{}\"", arg.replace('\\', "\\\\").replace('"', "\\\""))
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Asignaciones de la sección del Quadlet, marcando las que se consultan
+struct Keys<'a> {
+    entries: Vec<(&'a str, &'a str)>,
+    used: HashSet<&'a str>,
+}
+
+impl<'a> Keys<'a> {
+    fn new(file: &'a UnitFile, section: &str) -> Self {
+        let entries = file
+            .entries()
+            .into_iter()
+            .filter(|e| e.section == Some(section))
+            .map(|e| (e.key, e.value))
+            .collect();
+        Self {
+            entries,
+            used: HashSet::new(),
+        }
+    }
+
+    /// Todos los valores de la clave, en orden. Una asignación vacía borra
+    /// las anteriores, como en systemd.
+    fn all(&mut self, key: &'a str) -> Vec<String> {
+        self.used.insert(key);
+        let mut values = Vec::new();
+        for (_, value) in self.entries.iter().filter(|(k, _)| *k == key) {
+            if value.trim().is_empty() {
+                values.clear();
+            } else {
+                values.push(value.to_string());
+            }
+        }
+        values
+    }
+
+    /// Último valor de la clave, que es el que cuenta
+    fn last(&mut self, key: &'a str) -> Option<&'a str> {
+        self.used.insert(key);
+        self.entries
+            .iter()
+            .rev()
+            .find(|(k, _)| *k == key)
+            .map(|(_, v)| *v)
+            .filter(|v| !v.is_empty())
+    }
+
+    /// Palabras de todos los valores de la clave (`Environment=A=1 "B=2 3"`)
+    fn words(&mut self, key: &'a str) -> Vec<String> {
+        self.all(key).iter().flat_map(|v| split_words(v)).collect()
+    }
+
+    /// Indica si la clave está activada
+    fn flag(&mut self, key: &'a str) -> bool {
+        self.last(key).and_then(parse_bool).unwrap_or(false)
+    }
+
+    /// Claves que no se han consultado, sin repetir
+    fn ignored(&self, section: &str) -> Vec<String> {
+        let mut ignored: Vec<String> = Vec::new();
+        for (key, _) in &self.entries {
+            let name = format!("{}.{}", section, key);
+            if !self.used.contains(key) && !ignored.contains(&name) {
+                ignored.push(name);
+            }
+        }
+        ignored
+    }
+}
+
+/// Unidad que se va construyendo
+struct Unit {
+    /// Secciones del Quadlet que pasan tal cual (`Unit`, `Service`,
+    /// `Install`...), en orden de aparición
+    sections: Vec<(String, Vec<(String, String)>)>,
+    /// Dependencias de otros Quadlets, que van en `[Unit]`
+    dependencies: Vec<(String, String)>,
+    /// Asignaciones generadas de `[Service]`; las del Quadlet van después
+    /// para que tengan prioridad
+    service: Vec<(String, String)>,
+    source_path: String,
+}
+
+impl Unit {
+    fn new(file: &UnitFile, quadlet_section: &str, source_path: &str) -> Self {
+        let mut sections: Vec<(String, Vec<(String, String)>)> = Vec::new();
+        for entry in file.entries() {
+            let Some(section) = entry.section.filter(|s| *s != quadlet_section) else {
+                continue;
+            };
+            let pair = (entry.key.to_string(), entry.value.to_string());
+            match sections.iter_mut().find(|(name, _)| name == section) {
+                Some((_, entries)) => entries.push(pair),
+                None => sections.push((section.to_string(), vec![pair])),
+            }
+        }
+        Self {
+            sections,
+            dependencies: Vec::new(),
+            service: Vec::new(),
+            source_path: source_path.to_string(),
+        }
+    }
+
+    fn render(mut self) -> String {
+        let mut unit = self.take("Unit");
+        unit.extend(self.dependencies);
+        unit.extend([
+            ("SourcePath".to_string(), self.source_path),
+            ("RequiresMountsFor".to_string(), "%t/containers".to_string()),
+        ]);
+        let mut service = self.service;
+        service.extend(
+            self.sections
+                .iter()
+                .position(|(name, _)| name == "Service")
+                .map(|index| self.sections.remove(index).1)
+                .unwrap_or_default(),
+        );
+
+        let mut output = String::from("# Automatically generated by QuMa\n");
+        let sections = [("Unit".to_string(), unit), ("Service".to_string(), service)]
+            .into_iter()
+            .chain(self.sections);
+        for (name, entries) in sections {
+            output.push_str(&format!("\n[{}]\n", name));
+            for (key, value) in entries {
+                output.push_str(&format!("{}={}\n", key, value));
+            }
+        }
+        output
+    }
+
+    /// Saca las asignaciones de una sección del Quadlet
+    fn take(&mut self, section: &str) -> Vec<(String, String)> {
+        match self.sections.iter().position(|(name, _)| name == section) {
+            Some(index) => self.sections.remove(index).1,
+            None => Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::DropIn;
+    use std::path::PathBuf;
+
+    fn quadlet(file_name: &str, content: &str) -> Quadlet {
+        let (name, extension) = file_name.split_once('.').unwrap();
+        let kind = QuadletType::from_extension(&format!(".{}", extension)).unwrap();
+        Quadlet::new(
+            name.to_string(),
+            kind,
+            content.to_string(),
+            PathBuf::from(format!("/q/{}", file_name)),
+        )
+    }
+
+    #[test]
+    fn test_translate_container() {
+        let web = quadlet(
+            "web.container",
+            "[Unit]\nDescription=Web\n\n[Container]\nImage=docker.io/nginx:1.27\n\
+             PublishPort=8080:80\nVolume=data.volume:/usr/share/nginx/html:Z\n\
+             Network=web.network\nEnvironment=TZ=Europe/Madrid \"GREETING=hola mundo\"\n\
+             ReadOnly=yes\nAddCapability=NET_ADMIN\nExec=nginx -g \"daemon off;\"\n\
+             Frobnicate=yes\n\n[Service]\nRestart=always\n\n[Install]\nWantedBy=default.target\n",
+        );
+
+        let translation = translate(&web).unwrap();
+        assert_eq!(translation.unit, "web.service");
+        assert_eq!(
+            translation.command.join(" "),
+            "/usr/bin/podman run --name systemd-%N --cidfile=%t/%N.cid --replace --rm \
+             --cgroups=split --sdnotify=conmon -d --publish 8080:80 \
+             -v systemd-data:/usr/share/nginx/html:Z --network systemd-web \
+             --env TZ=Europe/Madrid --env GREETING=hola mundo --cap-add net_admin \
+             --read-only docker.io/nginx:1.27 nginx -g daemon off;"
+        );
+        assert_eq!(translation.ignored, ["Container.Frobnicate"]);
+
+        let unit = UnitFile::parse(&translation.content);
+        assert_eq!(unit.get("Unit", "Description"), Some("Web"));
+        assert_eq!(
+            unit.get_all("Unit", "Requires"),
+            ["data-volume.service", "web-network.service"]
+        );
+        assert_eq!(unit.get("Unit", "SourcePath"), Some("/q/web.container"));
+        assert_eq!(unit.get("Service", "Type"), Some("notify"));
+        assert_eq!(unit.get("Service", "Restart"), Some("always"));
+        assert_eq!(unit.get("Install", "WantedBy"), Some("default.target"));
+        assert!(
+            unit.get("Service", "ExecStart")
+                .unwrap()
+                .contains("--env \"GREETING=hola mundo\"")
+        );
+        assert!(!unit.has_section("Container"));
+    }
+
+    #[test]
+    fn test_translate_container_with_dropins() {
+        let mut web = quadlet(
+            "web.container",
+            "[Container]\nImage=nginx:1.25\nPod=app.pod\n",
+        );
+        web.dropins.push(DropIn::new(
+            "web.container.d/10-image.conf".to_string(),
+            "[Container]\nImage=nginx:1.27\nServiceName=site\n".to_string(),
+            PathBuf::from("/q/web.container.d/10-image.conf"),
+        ));
+
+        let translation = translate(&web).unwrap();
+        assert_eq!(translation.unit, "site.service");
+        assert_eq!(translation.command.last().unwrap(), "nginx:1.27");
+        assert!(translation.ignored.is_empty());
+        let unit = UnitFile::parse(&translation.content);
+        assert_eq!(unit.get("Unit", "BindsTo"), Some("app-pod.service"));
+    }
+
+    #[test]
+    fn test_translate_container_without_image() {
+        let error =
+            translate(&quadlet("web.container", "[Container]\nExec=sleep 1\n")).unwrap_err();
+        assert_eq!(error, "No Image key specified");
+    }
+
+    #[test]
+    fn test_translate_volume_network_and_pod() {
+        let translation = translate(&quadlet(
+            "data.volume",
+            "[Volume]\nDevice=/srv/data\nType=none\nOptions=bind\nCopy=no\nLabel=app=web\n",
+        ))
+        .unwrap();
+        assert_eq!(translation.unit, "data-volume.service");
+        assert_eq!(
+            translation.command.join(" "),
+            "/usr/bin/podman volume create --ignore --opt type=none --opt device=/srv/data \
+             --opt o=bind --opt nocopy --label app=web systemd-data"
+        );
+        let unit = UnitFile::parse(&translation.content);
+        assert_eq!(unit.get("Service", "Type"), Some("oneshot"));
+        assert_eq!(unit.get("Service", "RemainAfterExit"), Some("yes"));
+
+        let translation = translate(&quadlet(
+            "web.network",
+            "[Network]\nNetworkName=web\nSubnet=10.89.0.0/24\nInternal=true\n",
+        ))
+        .unwrap();
+        assert_eq!(
+            translation.command.join(" "),
+            "/usr/bin/podman network create --ignore --subnet 10.89.0.0/24 --internal web"
+        );
+
+        let translation = translate(&quadlet(
+            "app.pod",
+            "[Pod]\nPublishPort=8080:80\nNetwork=web.network\n",
+        ))
+        .unwrap();
+        assert_eq!(translation.unit, "app-pod.service");
+        assert!(translation.command.ends_with(&[
+            "--publish".to_string(),
+            "8080:80".to_string(),
+            "--network".to_string(),
+            "systemd-web".to_string(),
+            "--name".to_string(),
+            "systemd-app".to_string(),
+        ]));
+        let unit = UnitFile::parse(&translation.content);
+        assert_eq!(unit.get("Unit", "Requires"), Some("web-network.service"));
+        assert_eq!(unit.get("Service", "Type"), Some("forking"));
+    }
+
+    #[test]
+    fn test_translate_unsupported() {
+        let error = translate(&quadlet("app.kube", "[Kube]\nYaml=app.yaml\n")).unwrap_err();
+        assert_eq!(
+            error,
+            "Kube quadlets are not supported by the native translator"
+        );
+    }
+
+    #[test]
+    fn test_exec_line_quoting() {
+        let args = ["podman", "--env", "A=hola mundo", "say \"hi\"", ""].map(String::from);
+        assert_eq!(
+            exec_line(&args),
+            "podman --env \"A=hola mundo\" \"say \\\"hi\\\"\" \"\""
+        );
+    }
+}
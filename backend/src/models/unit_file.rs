@@ -0,0 +1,481 @@
+use serde::Serialize;
+use std::fmt;
+
+/// Línea lógica de un archivo Quadlet (formato INI de systemd).
+///
+/// Cada variante guarda el texto original (`raw`), incluidos los saltos de
+/// línea y las continuaciones, para poder reescribir el archivo byte a byte.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Line {
+    /// Línea vacía o formada solo por espacios
+    Blank { raw: String },
+    /// Comentario que empieza por `#` o `;`
+    Comment { raw: String },
+    /// Cabecera de sección, p. ej. `[Container]`
+    Section { raw: String, name: String },
+    /// Asignación `Clave=Valor`, posiblemente con continuaciones `\`
+    Entry {
+        raw: String,
+        key: String,
+        value: String,
+    },
+    /// Línea que no se ha podido interpretar
+    Invalid { raw: String },
+}
+
+impl Line {
+    /// Devuelve el texto original de la línea
+    pub fn raw(&self) -> &str {
+        match self {
+            Line::Blank { raw }
+            | Line::Comment { raw }
+            | Line::Section { raw, .. }
+            | Line::Entry { raw, .. }
+            | Line::Invalid { raw } => raw,
+        }
+    }
+}
+
+/// Línea lógica junto a su posición en el archivo
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpannedLine {
+    /// Número de la primera línea física (empezando en 1)
+    pub line: usize,
+    pub content: Line,
+}
+
+/// Asignación dentro de una sección, con su posición en el archivo
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Entry<'a> {
+    /// Sección a la que pertenece, `None` si aparece antes de cualquier cabecera
+    pub section: Option<&'a str>,
+    pub key: &'a str,
+    pub value: &'a str,
+    /// Línea (empezando en 1)
+    pub line: usize,
+    /// Columna donde empieza la clave (empezando en 1)
+    pub column: usize,
+    /// Columna donde empieza el valor (empezando en 1)
+    pub value_column: usize,
+}
+
+/// Archivo Quadlet interpretado como secciones, claves y valores.
+///
+/// El parser nunca falla: las líneas que no entiende se guardan como
+/// [`Line::Invalid`] para que la validación pueda informar de ellas.
+/// `to_string()` devuelve exactamente el texto de entrada mientras no se
+/// modifique el archivo.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UnitFile {
+    lines: Vec<SpannedLine>,
+}
+
+impl UnitFile {
+    /// Interpreta el contenido de un archivo Quadlet
+    pub fn parse(content: &str) -> Self {
+        let mut lines = Vec::new();
+        let mut physical = content.split_inclusive('\n');
+        let mut line_number = 0;
+
+        while let Some(first) = physical.next() {
+            line_number += 1;
+            let start = line_number;
+            let text = strip_newline(first);
+            let trimmed = text.trim();
+
+            let content = if trimmed.is_empty() {
+                Line::Blank {
+                    raw: first.to_string(),
+                }
+            } else if trimmed.starts_with('#') || trimmed.starts_with(';') {
+                Line::Comment {
+                    raw: first.to_string(),
+                }
+            } else if trimmed.starts_with('[') {
+                match trimmed.strip_suffix(']') {
+                    Some(name) => Line::Section {
+                        raw: first.to_string(),
+                        name: name[1..].to_string(),
+                    },
+                    None => Line::Invalid {
+                        raw: first.to_string(),
+                    },
+                }
+            } else if let Some((key, value)) = text.split_once('=') {
+                let mut raw = first.to_string();
+                let mut value = value.trim().to_string();
+                // Continuaciones: una barra invertida final une la siguiente línea
+                while let Some(stripped) = value.strip_suffix('\\') {
+                    let Some(next) = physical.next() else {
+                        value = stripped.trim_end().to_string();
+                        break;
+                    };
+                    line_number += 1;
+                    raw.push_str(next);
+                    let next_text = strip_newline(next).trim();
+                    if next_text.starts_with('#') || next_text.starts_with(';') {
+                        continue;
+                    }
+                    value = format!("{} {}", stripped.trim_end(), next_text);
+                }
+                Line::Entry {
+                    raw,
+                    key: key.trim().to_string(),
+                    value,
+                }
+            } else {
+                Line::Invalid {
+                    raw: first.to_string(),
+                }
+            };
+            lines.push(SpannedLine {
+                line: start,
+                content,
+            });
+        }
+
+        Self { lines }
+    }
+
+    /// Devuelve las líneas lógicas del archivo
+    pub fn lines(&self) -> &[SpannedLine] {
+        &self.lines
+    }
+
+    /// Devuelve los nombres de las secciones en orden de aparición
+    pub fn sections(&self) -> Vec<&str> {
+        let mut sections: Vec<&str> = Vec::new();
+        for spanned in &self.lines {
+            if let Line::Section { name, .. } = &spanned.content
+                && !sections.contains(&name.as_str())
+            {
+                sections.push(name);
+            }
+        }
+        sections
+    }
+
+    /// Devuelve todas las asignaciones en orden de aparición
+    pub fn entries(&self) -> Vec<Entry<'_>> {
+        let mut section: Option<&str> = None;
+        let mut entries = Vec::new();
+        for spanned in &self.lines {
+            match &spanned.content {
+                Line::Section { name, .. } => section = Some(name),
+                Line::Entry { raw, key, value } => {
+                    // Las columnas se cuentan en caracteres, no en bytes
+                    let indent = raw.chars().count() - raw.trim_start().chars().count();
+                    let (before, after) = raw.split_once('=').unwrap_or((raw, ""));
+                    let value_offset = after.chars().count() - after.trim_start().chars().count();
+                    entries.push(Entry {
+                        section,
+                        key,
+                        value,
+                        line: spanned.line,
+                        column: indent + 1,
+                        value_column: before.chars().count() + 1 + value_offset + 1,
+                    });
+                }
+                _ => {}
+            }
+        }
+        entries
+    }
+
+    /// Devuelve el último valor asignado a una clave (semántica de systemd)
+    pub fn get(&self, section: &str, key: &str) -> Option<&str> {
+        self.entries()
+            .into_iter()
+            .rev()
+            .find(|e| e.section == Some(section) && e.key == key)
+            .map(|e| e.value)
+    }
+
+    /// Devuelve todos los valores de una clave repetible, como `Volume=`
+    pub fn get_all(&self, section: &str, key: &str) -> Vec<&str> {
+        self.entries()
+            .into_iter()
+            .filter(|e| e.section == Some(section) && e.key == key)
+            .map(|e| e.value)
+            .collect()
+    }
+
+    /// Indica si el archivo contiene la sección indicada
+    pub fn has_section(&self, section: &str) -> bool {
+        self.sections().contains(&section)
+    }
+
+    /// Sustituye todas las apariciones de una clave por un único valor.
+    ///
+    /// La nueva asignación ocupa el lugar de la primera aparición; si la clave
+    /// no existe se añade al final de la sección, creándola si hace falta.
+    pub fn set(&mut self, section: &str, key: &str, value: &str) {
+        let positions = self.positions(section, key);
+        match positions.split_first() {
+            Some((first, rest)) => {
+                self.lines[*first].content = entry_line(key, value);
+                for index in rest.iter().rev() {
+                    self.lines.remove(*index);
+                }
+                self.renumber();
+            }
+            None => self.append(section, key, value),
+        }
+    }
+
+    /// Añade una asignación al final de la sección, creándola si no existe
+    pub fn append(&mut self, section: &str, key: &str, value: &str) {
+        let index = match self.section_end(section) {
+            Some(index) => index,
+            None => {
+                self.ensure_trailing_newline();
+                if !self.lines.is_empty() {
+                    self.lines.push(SpannedLine {
+                        line: 0,
+                        content: Line::Blank {
+                            raw: "\n".to_string(),
+                        },
+                    });
+                }
+                self.lines.push(SpannedLine {
+                    line: 0,
+                    content: Line::Section {
+                        raw: format!("[{}]\n", section),
+                        name: section.to_string(),
+                    },
+                });
+                self.lines.len()
+            }
+        };
+        if index == self.lines.len() {
+            self.ensure_trailing_newline();
+        }
+        self.lines.insert(
+            index,
+            SpannedLine {
+                line: 0,
+                content: entry_line(key, value),
+            },
+        );
+        self.renumber();
+    }
+
+    /// Elimina todas las apariciones de una clave. Devuelve cuántas se eliminaron
+    pub fn remove(&mut self, section: &str, key: &str) -> usize {
+        let positions = self.positions(section, key);
+        for index in positions.iter().rev() {
+            self.lines.remove(*index);
+        }
+        self.renumber();
+        positions.len()
+    }
+
+    /// Índices de las líneas con la clave indicada dentro de la sección
+    fn positions(&self, section: &str, key: &str) -> Vec<usize> {
+        let mut current: Option<&str> = None;
+        let mut positions = Vec::new();
+        for (index, spanned) in self.lines.iter().enumerate() {
+            match &spanned.content {
+                Line::Section { name, .. } => current = Some(name),
+                Line::Entry { key: k, .. } if current == Some(section) && k == key => {
+                    positions.push(index)
+                }
+                _ => {}
+            }
+        }
+        positions
+    }
+
+    /// Índice justo después de la última asignación de la última aparición de
+    /// la sección, o `None` si la sección no existe
+    fn section_end(&self, section: &str) -> Option<usize> {
+        let mut current: Option<&str> = None;
+        let mut end = None;
+        for (index, spanned) in self.lines.iter().enumerate() {
+            match &spanned.content {
+                Line::Section { name, .. } => {
+                    current = Some(name);
+                    if name == section {
+                        end = Some(index + 1);
+                    }
+                }
+                Line::Entry { .. } if current == Some(section) => end = Some(index + 1),
+                _ => {}
+            }
+        }
+        end
+    }
+
+    fn ensure_trailing_newline(&mut self) {
+        if let Some(last) = self.lines.last_mut()
+            && !last.content.raw().ends_with('\n')
+        {
+            let raw = format!("{}\n", last.content.raw());
+            last.content = match &last.content {
+                Line::Blank { .. } => Line::Blank { raw },
+                Line::Comment { .. } => Line::Comment { raw },
+                Line::Section { name, .. } => Line::Section {
+                    raw,
+                    name: name.clone(),
+                },
+                Line::Entry { key, value, .. } => Line::Entry {
+                    raw,
+                    key: key.clone(),
+                    value: value.clone(),
+                },
+                Line::Invalid { .. } => Line::Invalid { raw },
+            };
+        }
+    }
+
+    fn renumber(&mut self) {
+        let mut line = 1;
+        for spanned in &mut self.lines {
+            spanned.line = line;
+            line += spanned.content.raw().matches('\n').count().max(1);
+        }
+    }
+}
+
+impl fmt::Display for UnitFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for spanned in &self.lines {
+            f.write_str(spanned.content.raw())?;
+        }
+        Ok(())
+    }
+}
+
+fn strip_newline(line: &str) -> &str {
+    let line = line.strip_suffix('\n').unwrap_or(line);
+    line.strip_suffix('\r').unwrap_or(line)
+}
+
+fn entry_line(key: &str, value: &str) -> Line {
+    Line::Entry {
+        raw: format!("{}={}\n", key, value),
+        key: key.to_string(),
+        value: value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "# Servidor web\n[Unit]\nDescription=Nginx\n\n[Container]\nImage=docker.io/nginx:latest\nPublishPort=8080:80\nVolume=data.volume:/data\nVolume=/etc/nginx:/etc/nginx:ro\nExec=nginx \\\n  -g 'daemon off;'\n; comentario\n\n[Install]\nWantedBy=default.target";
+
+    #[test]
+    fn test_round_trip_is_byte_identical() {
+        let unit = UnitFile::parse(SAMPLE);
+        assert_eq!(unit.to_string(), SAMPLE);
+
+        let crlf = "[Container]\r\nImage=alpine\r\n\r\n";
+        assert_eq!(UnitFile::parse(crlf).to_string(), crlf);
+    }
+
+    #[test]
+    fn test_sections_and_values() {
+        let unit = UnitFile::parse(SAMPLE);
+        assert_eq!(unit.sections(), vec!["Unit", "Container", "Install"]);
+        assert_eq!(
+            unit.get("Container", "Image"),
+            Some("docker.io/nginx:latest")
+        );
+        assert_eq!(unit.get("Unit", "Image"), None);
+        assert_eq!(unit.get("Install", "WantedBy"), Some("default.target"));
+    }
+
+    #[test]
+    fn test_repeated_keys() {
+        let unit = UnitFile::parse(SAMPLE);
+        assert_eq!(
+            unit.get_all("Container", "Volume"),
+            vec!["data.volume:/data", "/etc/nginx:/etc/nginx:ro"]
+        );
+        assert_eq!(
+            unit.get("Container", "Volume"),
+            Some("/etc/nginx:/etc/nginx:ro")
+        );
+    }
+
+    #[test]
+    fn test_line_continuation() {
+        let unit = UnitFile::parse(SAMPLE);
+        assert_eq!(
+            unit.get("Container", "Exec"),
+            Some("nginx -g 'daemon off;'")
+        );
+
+        let entries = unit.entries();
+        let exec = entries.iter().find(|e| e.key == "Exec").unwrap();
+        assert_eq!(exec.line, 10);
+        let comment = unit
+            .lines()
+            .iter()
+            .find(|l| matches!(l.content, Line::Comment { .. }) && l.line > 1)
+            .unwrap();
+        assert_eq!(comment.line, 12);
+    }
+
+    #[test]
+    fn test_entry_positions() {
+        let unit = UnitFile::parse("[Container]\n  Image = alpine\n");
+        let entry = &unit.entries()[0];
+        assert_eq!(entry.line, 2);
+        assert_eq!(entry.column, 3);
+        assert_eq!(entry.value_column, 11);
+        assert_eq!(entry.value, "alpine");
+
+        // Espacios de más de un byte
+        let unit = UnitFile::parse("[Container]\n\u{3000}Image=\u{a0}alpine\n");
+        let entry = &unit.entries()[0];
+        assert_eq!(entry.column, 2);
+        assert_eq!(entry.value_column, 9);
+    }
+
+    #[test]
+    fn test_invalid_lines_are_preserved() {
+        let content = "[Container\nImage=alpine\ngarbage\n";
+        let unit = UnitFile::parse(content);
+        assert_eq!(unit.to_string(), content);
+        let invalid = unit
+            .lines()
+            .iter()
+            .filter(|l| matches!(l.content, Line::Invalid { .. }))
+            .count();
+        assert_eq!(invalid, 2);
+    }
+
+    #[test]
+    fn test_set_replaces_and_preserves_comments() {
+        let mut unit = UnitFile::parse(SAMPLE);
+        unit.set("Container", "Volume", "other.volume:/other");
+        assert_eq!(
+            unit.get_all("Container", "Volume"),
+            vec!["other.volume:/other"]
+        );
+        assert!(unit.to_string().starts_with("# Servidor web\n"));
+        assert!(unit.to_string().contains("; comentario\n"));
+    }
+
+    #[test]
+    fn test_append_creates_section() {
+        let mut unit = UnitFile::parse("[Container]\nImage=alpine");
+        unit.append("Container", "PublishPort", "80:80");
+        unit.append("Service", "Restart", "always");
+        assert_eq!(
+            unit.to_string(),
+            "[Container]\nImage=alpine\nPublishPort=80:80\n\n[Service]\nRestart=always\n"
+        );
+        assert_eq!(unit.entries()[2].line, 6);
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut unit = UnitFile::parse(SAMPLE);
+        assert_eq!(unit.remove("Container", "Volume"), 2);
+        assert!(unit.get_all("Container", "Volume").is_empty());
+        assert_eq!(unit.remove("Container", "Volume"), 0);
+    }
+}
@@ -0,0 +1,677 @@
+use serde::{Deserialize, Serialize};
+
+use super::{Line, QuadletType, UnitFile};
+
+/// Gravedad de un diagnóstico
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+    Info,
+}
+
+/// Problema detectado al validar un archivo Quadlet
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// Línea (empezando en 1)
+    pub line: usize,
+    /// Columna (empezando en 1)
+    pub column: usize,
+    /// Código estable legible por máquina, p. ej. `UNKNOWN_KEY`
+    pub code: String,
+    pub message: String,
+    /// Corrección sugerida, si existe
+    pub suggestion: Option<String>,
+}
+
+impl Diagnostic {
+    fn new(severity: Severity, line: usize, column: usize, code: &str, message: String) -> Self {
+        Self {
+            severity,
+            line,
+            column,
+            code: code.to_string(),
+            message,
+            suggestion: None,
+        }
+    }
+
+    fn with_suggestion(mut self, suggestion: Option<String>) -> Self {
+        self.suggestion = suggestion;
+        self
+    }
+
+    /// Indica si el diagnóstico impide guardar el archivo
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+/// Formato esperado para el valor de una clave
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ValueFormat {
+    Any,
+    NonEmpty,
+    Bool,
+    Integer,
+    Port,
+    KeyValue,
+    OneOf(&'static [&'static str]),
+}
+
+/// Clave admitida en la sección principal de un tipo de Quadlet
+struct KeySpec {
+    name: &'static str,
+    format: ValueFormat,
+}
+
+const fn key(name: &'static str, format: ValueFormat) -> KeySpec {
+    KeySpec { name, format }
+}
+
+use ValueFormat::{Any, Bool, Integer, KeyValue, NonEmpty, OneOf, Port};
+
+const PULL_POLICIES: &[&str] = &["always", "missing", "never", "newer"];
+const AUTO_UPDATE: &[&str] = &["registry", "local"];
+
+/// Claves de `[Container]` según podman-systemd.unit(5)
+const CONTAINER_KEYS: &[KeySpec] = &[
+    key("AddCapability", NonEmpty),
+    key("AddDevice", NonEmpty),
+    key("AddHost", NonEmpty),
+    key("Annotation", KeyValue),
+    key("AutoUpdate", OneOf(AUTO_UPDATE)),
+    key("CgroupsMode", NonEmpty),
+    key("ContainerName", NonEmpty),
+    key("ContainersConfModule", NonEmpty),
+    key("DNS", NonEmpty),
+    key("DNSOption", NonEmpty),
+    key("DNSSearch", NonEmpty),
+    key("DropCapability", NonEmpty),
+    key("Entrypoint", NonEmpty),
+    key("Environment", KeyValue),
+    key("EnvironmentFile", NonEmpty),
+    key("EnvironmentHost", Bool),
+    key("Exec", Any),
+    key("ExposeHostPort", NonEmpty),
+    key("GIDMap", NonEmpty),
+    key("GlobalArgs", Any),
+    key("Group", NonEmpty),
+    key("GroupAdd", NonEmpty),
+    key("HealthCmd", NonEmpty),
+    key("HealthInterval", NonEmpty),
+    key("HealthLogDestination", NonEmpty),
+    key("HealthMaxLogCount", Integer),
+    key("HealthMaxLogSize", Integer),
+    key(
+        "HealthOnFailure",
+        OneOf(&["none", "kill", "restart", "stop"]),
+    ),
+    key("HealthRetries", Integer),
+    key("HealthStartPeriod", NonEmpty),
+    key("HealthStartupCmd", NonEmpty),
+    key("HealthStartupInterval", NonEmpty),
+    key("HealthStartupRetries", Integer),
+    key("HealthStartupSuccess", Integer),
+    key("HealthStartupTimeout", NonEmpty),
+    key("HealthTimeout", NonEmpty),
+    key("HostName", NonEmpty),
+    key("HttpProxy", Bool),
+    key("Image", NonEmpty),
+    key("IP", NonEmpty),
+    key("IP6", NonEmpty),
+    key("Label", KeyValue),
+    key("LogDriver", NonEmpty),
+    key("LogOpt", NonEmpty),
+    key("Mask", NonEmpty),
+    key("Memory", NonEmpty),
+    key("Mount", NonEmpty),
+    key("Network", NonEmpty),
+    key("NetworkAlias", NonEmpty),
+    key("NoNewPrivileges", Bool),
+    key("Notify", OneOf(&["true", "false", "yes", "no", "healthy"])),
+    key("PidsLimit", Integer),
+    key("Pod", NonEmpty),
+    key("PodmanArgs", Any),
+    key("PublishPort", Port),
+    key("Pull", OneOf(PULL_POLICIES)),
+    key("ReadOnly", Bool),
+    key("ReadOnlyTmpfs", Bool),
+    key("ReloadCmd", NonEmpty),
+    key("ReloadSignal", NonEmpty),
+    key("Retry", Integer),
+    key("RetryDelay", NonEmpty),
+    key("Rootfs", NonEmpty),
+    key("RunInit", Bool),
+    key("SeccompProfile", NonEmpty),
+    key("Secret", NonEmpty),
+    key("SecurityLabelDisable", Bool),
+    key("SecurityLabelFileType", NonEmpty),
+    key("SecurityLabelLevel", NonEmpty),
+    key("SecurityLabelNested", Bool),
+    key("SecurityLabelType", NonEmpty),
+    key("ServiceName", NonEmpty),
+    key("ShmSize", NonEmpty),
+    key("StartWithPod", Bool),
+    key("StopSignal", NonEmpty),
+    key("StopTimeout", Integer),
+    key("SubGIDMap", NonEmpty),
+    key("SubUIDMap", NonEmpty),
+    key("Sysctl", KeyValue),
+    key("Timezone", NonEmpty),
+    key("Tmpfs", NonEmpty),
+    key("UIDMap", NonEmpty),
+    key("Ulimit", NonEmpty),
+    key("Unmask", NonEmpty),
+    key("User", NonEmpty),
+    key("UserNS", NonEmpty),
+    key("Volume", NonEmpty),
+    key("WorkingDir", NonEmpty),
+];
+
+/// Claves de `[Network]`
+const NETWORK_KEYS: &[KeySpec] = &[
+    key("ContainersConfModule", NonEmpty),
+    key("DisableDNS", Bool),
+    key("DNS", NonEmpty),
+    key("Driver", OneOf(&["bridge", "macvlan", "ipvlan"])),
+    key("Gateway", NonEmpty),
+    key("GlobalArgs", Any),
+    key("InterfaceName", NonEmpty),
+    key("Internal", Bool),
+    key("IPAMDriver", OneOf(&["host-local", "dhcp", "none"])),
+    key("IPRange", NonEmpty),
+    key("IPv6", Bool),
+    key("Label", KeyValue),
+    key("NetworkDeleteOnStop", Bool),
+    key("NetworkName", NonEmpty),
+    key("Options", NonEmpty),
+    key("PodmanArgs", Any),
+    key("ServiceName", NonEmpty),
+    key("Subnet", NonEmpty),
+];
+
+/// Claves de `[Volume]`
+const VOLUME_KEYS: &[KeySpec] = &[
+    key("ContainersConfModule", NonEmpty),
+    key("Copy", Bool),
+    key("Device", NonEmpty),
+    key("Driver", NonEmpty),
+    key("GlobalArgs", Any),
+    key("Group", NonEmpty),
+    key("Image", NonEmpty),
+    key("Label", KeyValue),
+    key("Options", NonEmpty),
+    key("PodmanArgs", Any),
+    key("ServiceName", NonEmpty),
+    key("Type", NonEmpty),
+    key("User", NonEmpty),
+    key("VolumeName", NonEmpty),
+];
+
+/// Claves de `[Kube]`
+const KUBE_KEYS: &[KeySpec] = &[
+    key("AutoUpdate", NonEmpty),
+    key("ConfigMap", NonEmpty),
+    key("ContainersConfModule", NonEmpty),
+    key("ExitCodePropagation", OneOf(&["all", "any", "none"])),
+    key("GlobalArgs", Any),
+    key("KubeDownForce", Bool),
+    key("LogDriver", NonEmpty),
+    key("Network", NonEmpty),
+    key("PodmanArgs", Any),
+    key("PublishPort", Port),
+    key("ServiceName", NonEmpty),
+    key("SetWorkingDirectory", OneOf(&["yaml", "unit"])),
+    key("UserNS", NonEmpty),
+    key("Yaml", NonEmpty),
+];
+
+/// Claves de `[Pod]`
+const POD_KEYS: &[KeySpec] = &[
+    key("AddHost", NonEmpty),
+    key("ContainersConfModule", NonEmpty),
+    key("DNS", NonEmpty),
+    key("DNSOption", NonEmpty),
+    key("DNSSearch", NonEmpty),
+    key("GIDMap", NonEmpty),
+    key("GlobalArgs", Any),
+    key("HostName", NonEmpty),
+    key("IP", NonEmpty),
+    key("IP6", NonEmpty),
+    key("Label", KeyValue),
+    key("Network", NonEmpty),
+    key("NetworkAlias", NonEmpty),
+    key("PodmanArgs", Any),
+    key("PodName", NonEmpty),
+    key("PublishPort", Port),
+    key("ServiceName", NonEmpty),
+    key("ShmSize", NonEmpty),
+    key("SubGIDMap", NonEmpty),
+    key("SubUIDMap", NonEmpty),
+    key("UIDMap", NonEmpty),
+    key("UserNS", NonEmpty),
+    key("Volume", NonEmpty),
+];
+
+/// Claves de `[Image]`
+const IMAGE_KEYS: &[KeySpec] = &[
+    key("AllTags", Bool),
+    key("Arch", NonEmpty),
+    key("AuthFile", NonEmpty),
+    key("CertDir", NonEmpty),
+    key("ContainersConfModule", NonEmpty),
+    key("Creds", NonEmpty),
+    key("DecryptionKey", NonEmpty),
+    key("GlobalArgs", Any),
+    key("Image", NonEmpty),
+    key("ImageTag", NonEmpty),
+    key("OS", NonEmpty),
+    key("PodmanArgs", Any),
+    key("Policy", OneOf(PULL_POLICIES)),
+    key("Retry", Integer),
+    key("RetryDelay", NonEmpty),
+    key("ServiceName", NonEmpty),
+    key("TLSVerify", Bool),
+    key("Variant", NonEmpty),
+];
+
+/// Secciones de systemd que cualquier Quadlet puede contener
+const SYSTEMD_SECTIONS: &[&str] = &["Unit", "Service", "Install", "Quadlet"];
+
+const ALL_TYPES: &[QuadletType] = &[
+    QuadletType::Container,
+    QuadletType::Network,
+    QuadletType::Volume,
+    QuadletType::Kube,
+    QuadletType::Pod,
+    QuadletType::Image,
+];
+
+fn keys_for(kind: QuadletType) -> &'static [KeySpec] {
+    match kind {
+        QuadletType::Container => CONTAINER_KEYS,
+        QuadletType::Network => NETWORK_KEYS,
+        QuadletType::Volume => VOLUME_KEYS,
+        QuadletType::Kube => KUBE_KEYS,
+        QuadletType::Pod => POD_KEYS,
+        QuadletType::Image => IMAGE_KEYS,
+    }
+}
+
+/// Claves obligatorias: basta con que aparezca una de cada grupo
+fn required_for(kind: QuadletType) -> &'static [&'static [&'static str]] {
+    match kind {
+        QuadletType::Container => &[&["Image", "Rootfs"]],
+        QuadletType::Kube => &[&["Yaml"]],
+        QuadletType::Image => &[&["Image"]],
+        QuadletType::Network | QuadletType::Volume | QuadletType::Pod => &[],
+    }
+}
+
+/// Valida un archivo Quadlet ya interpretado contra el esquema de su tipo
+pub fn validate(kind: QuadletType, unit: &UnitFile) -> Vec<Diagnostic> {
+    let main_section = kind.section();
+    let keys = keys_for(kind);
+    let mut diagnostics = Vec::new();
+
+    for spanned in unit.lines() {
+        match &spanned.content {
+            Line::Invalid { raw } => {
+                let column = raw.len() - raw.trim_start().len() + 1;
+                diagnostics.push(Diagnostic::new(
+                    Severity::Error,
+                    spanned.line,
+                    column,
+                    "INVALID_LINE",
+                    format!(
+                        "Invalid line: expected `[Section]` or `Key=Value`, found `{}`",
+                        raw.trim()
+                    ),
+                ));
+            }
+            Line::Section { raw, name } => {
+                let column = raw.len() - raw.trim_start().len() + 1;
+                if name == main_section
+                    || SYSTEMD_SECTIONS.contains(&name.as_str())
+                    || name.starts_with("X-")
+                {
+                    continue;
+                }
+                if let Some(other) = ALL_TYPES.iter().find(|t| t.section() == name) {
+                    diagnostics.push(
+                        Diagnostic::new(
+                            Severity::Error,
+                            spanned.line,
+                            column,
+                            "WRONG_SECTION",
+                            format!(
+                                "Section [{}] belongs to {} files, not {} files",
+                                name,
+                                other.extension(),
+                                kind.extension()
+                            ),
+                        )
+                        .with_suggestion(Some(format!("[{}]", main_section))),
+                    );
+                } else {
+                    diagnostics.push(
+                        Diagnostic::new(
+                            Severity::Warning,
+                            spanned.line,
+                            column,
+                            "UNKNOWN_SECTION",
+                            format!("Unknown section [{}]", name),
+                        )
+                        .with_suggestion(
+                            closest(name, SYSTEMD_SECTIONS.iter().copied().chain([main_section]))
+                                .map(|s| format!("[{}]", s)),
+                        ),
+                    );
+                }
+            }
+            _ => {}
+        }
+    }
+
+    for entry in unit.entries() {
+        let Some(section) = entry.section else {
+            diagnostics.push(Diagnostic::new(
+                Severity::Error,
+                entry.line,
+                entry.column,
+                "KEY_OUTSIDE_SECTION",
+                format!("Key `{}` appears before any section header", entry.key),
+            ));
+            continue;
+        };
+        if section != main_section {
+            continue;
+        }
+        match keys.iter().find(|k| k.name == entry.key) {
+            Some(spec) => {
+                if let Some(message) = check_value(spec.format, entry.value) {
+                    diagnostics.push(Diagnostic::new(
+                        Severity::Error,
+                        entry.line,
+                        entry.value_column,
+                        "INVALID_VALUE",
+                        format!("Invalid value for {}: {}", entry.key, message),
+                    ));
+                }
+            }
+            None => {
+                let suggestion = closest(entry.key, keys.iter().map(|k| k.name));
+                diagnostics.push(
+                    Diagnostic::new(
+                        Severity::Error,
+                        entry.line,
+                        entry.column,
+                        "UNKNOWN_KEY",
+                        format!("Unknown key `{}` in section [{}]", entry.key, main_section),
+                    )
+                    .with_suggestion(suggestion.map(String::from)),
+                );
+            }
+        }
+    }
+
+    if !unit.has_section(main_section) {
+        diagnostics.push(
+            Diagnostic::new(
+                Severity::Error,
+                1,
+                1,
+                "MISSING_SECTION",
+                format!("Missing [{}] section", main_section),
+            )
+            .with_suggestion(Some(format!("[{}]", main_section))),
+        );
+    } else {
+        for group in required_for(kind) {
+            if group.iter().all(|k| unit.get(main_section, k).is_none()) {
+                let line = section_line(unit, main_section);
+                diagnostics.push(
+                    Diagnostic::new(
+                        Severity::Error,
+                        line,
+                        1,
+                        "MISSING_KEY",
+                        format!(
+                            "Section [{}] requires {}=",
+                            main_section,
+                            group.join("= or ")
+                        ),
+                    )
+                    .with_suggestion(Some(format!("{}=", group[0]))),
+                );
+            }
+        }
+    }
+
+    diagnostics.sort_by_key(|d| (d.line, d.column));
+    diagnostics
+}
+
+/// Comprueba el formato de un valor. Devuelve el motivo si no es válido
+fn check_value(format: ValueFormat, value: &str) -> Option<String> {
+    match format {
+        ValueFormat::Any => None,
+        ValueFormat::NonEmpty => value
+            .is_empty()
+            .then(|| "value cannot be empty".to_string()),
+        ValueFormat::Bool => {
+            const BOOLS: &[&str] = &["true", "false", "yes", "no", "on", "off", "1", "0"];
+            (!BOOLS.contains(&value.to_lowercase().as_str()))
+                .then(|| format!("expected a boolean, found `{}`", value))
+        }
+        ValueFormat::Integer => value
+            .parse::<i64>()
+            .is_err()
+            .then(|| format!("expected an integer, found `{}`", value)),
+        ValueFormat::OneOf(options) => (!options.contains(&value))
+            .then(|| format!("expected one of {}, found `{}`", options.join(", "), value)),
+        ValueFormat::KeyValue => {
+            (!value.contains('=')).then(|| format!("expected KEY=VALUE, found `{}`", value))
+        }
+        ValueFormat::Port => (!is_valid_port_mapping(value)).then(|| {
+            format!(
+                "expected [[ip:][hostPort]:]containerPort[/protocol], found `{}`",
+                value
+            )
+        }),
+    }
+}
+
+/// Valida un mapeo de puertos `[[ip:][hostPort]:]containerPort[/protocol]`
+fn is_valid_port_mapping(value: &str) -> bool {
+    let (mapping, protocol) = match value.rsplit_once('/') {
+        Some((mapping, protocol)) => (mapping, Some(protocol)),
+        None => (value, None),
+    };
+    if let Some(protocol) = protocol
+        && !["tcp", "udp", "sctp"].contains(&protocol)
+    {
+        return false;
+    }
+    // El puerto del contenedor es siempre el último segmento; una IPv6 va entre corchetes
+    let (host, container) = match mapping.rsplit_once(':') {
+        Some((host, container)) => (Some(host), container),
+        None => (None, mapping),
+    };
+    if !is_port_range(container) {
+        return false;
+    }
+    match host {
+        None => true,
+        Some(host) => {
+            let host_port = match host.rsplit_once(':') {
+                Some((_, port)) if !host.ends_with(']') => port,
+                _ if host.starts_with('[') || host.contains('.') => "",
+                _ => host,
+            };
+            host_port.is_empty() || is_port_range(host_port)
+        }
+    }
+}
+
+fn is_port_range(value: &str) -> bool {
+    let is_port = |p: &str| p.parse::<u16>().is_ok_and(|p| p > 0);
+    match value.split_once('-') {
+        Some((start, end)) => is_port(start) && is_port(end),
+        None => is_port(value),
+    }
+}
+
+fn section_line(unit: &UnitFile, section: &str) -> usize {
+    unit.lines()
+        .iter()
+        .find(|l| matches!(&l.content, Line::Section { name, .. } if name == section))
+        .map(|l| l.line)
+        .unwrap_or(1)
+}
+
+/// Busca el candidato más parecido (sin distinguir mayúsculas o a distancia ≤ 2)
+fn closest<'a>(name: &str, candidates: impl Iterator<Item = &'a str>) -> Option<&'a str> {
+    let lower = name.to_lowercase();
+    candidates
+        .map(|c| (levenshtein(&lower, &c.to_lowercase()), c))
+        .filter(|(distance, _)| *distance <= 2)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, c)| c)
+}
+
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = if ca == *cb { 0 } else { 1 };
+            current.push(
+                (previous[j] + cost)
+                    .min(previous[j + 1] + 1)
+                    .min(current[j] + 1),
+            );
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(kind: QuadletType, content: &str) -> Vec<Diagnostic> {
+        validate(kind, &UnitFile::parse(content))
+    }
+
+    fn codes(diagnostics: &[Diagnostic]) -> Vec<&str> {
+        diagnostics.iter().map(|d| d.code.as_str()).collect()
+    }
+
+    #[test]
+    fn test_valid_container() {
+        let content = "[Unit]\nDescription=Web\n\n[Container]\nImage=nginx\nPublishPort=8080:80\nPublishPort=127.0.0.1:443:443/tcp\nEnvironment=A=1 B=2\nReadOnly=true\n\n[Install]\nWantedBy=default.target\n";
+        assert!(check(QuadletType::Container, content).is_empty());
+    }
+
+    #[test]
+    fn test_unknown_key_with_suggestion() {
+        let diagnostics = check(
+            QuadletType::Container,
+            "[Container]\nImage=nginx\nPublishPorts=80:80\n",
+        );
+        assert_eq!(codes(&diagnostics), vec!["UNKNOWN_KEY"]);
+        assert_eq!(diagnostics[0].line, 3);
+        assert_eq!(diagnostics[0].column, 1);
+        assert_eq!(diagnostics[0].suggestion.as_deref(), Some("PublishPort"));
+        assert!(diagnostics[0].is_error());
+    }
+
+    #[test]
+    fn test_missing_mandatory_key() {
+        let diagnostics = check(QuadletType::Container, "[Container]\nPublishPort=80\n");
+        assert_eq!(codes(&diagnostics), vec!["MISSING_KEY"]);
+
+        let diagnostics = check(QuadletType::Kube, "[Kube]\n");
+        assert_eq!(codes(&diagnostics), vec!["MISSING_KEY"]);
+
+        assert!(check(QuadletType::Container, "[Container]\nRootfs=/srv/root\n").is_empty());
+    }
+
+    #[test]
+    fn test_wrong_and_missing_section() {
+        let diagnostics = check(QuadletType::Container, "[Network]\nSubnet=10.0.0.0/24\n");
+        assert_eq!(
+            codes(&diagnostics),
+            vec!["WRONG_SECTION", "MISSING_SECTION"]
+        );
+        assert_eq!(diagnostics[0].suggestion.as_deref(), Some("[Container]"));
+    }
+
+    #[test]
+    fn test_unknown_section_is_warning() {
+        let diagnostics = check(
+            QuadletType::Network,
+            "[Network]\n[Servcie]\nRestart=always\n",
+        );
+        assert_eq!(codes(&diagnostics), vec!["UNKNOWN_SECTION"]);
+        assert_eq!(diagnostics[0].severity, Severity::Warning);
+        assert_eq!(diagnostics[0].suggestion.as_deref(), Some("[Service]"));
+
+        assert!(check(QuadletType::Network, "[Network]\n[X-Custom]\nFoo=bar\n").is_empty());
+    }
+
+    #[test]
+    fn test_invalid_values() {
+        let content = "[Container]\nImage=nginx\nReadOnly=maybe\nPublishPort=http\nPull=sometimes\nLabel=novalue\nStopTimeout=ten\n";
+        let diagnostics = check(QuadletType::Container, content);
+        assert_eq!(codes(&diagnostics), vec!["INVALID_VALUE"; 5]);
+        assert_eq!(diagnostics[0].line, 3);
+        assert_eq!(diagnostics[0].column, 10);
+    }
+
+    #[test]
+    fn test_invalid_line_and_key_outside_section() {
+        let diagnostics = check(
+            QuadletType::Volume,
+            "Driver=local\n[Volume]\nthis is wrong\n",
+        );
+        assert_eq!(
+            codes(&diagnostics),
+            vec!["KEY_OUTSIDE_SECTION", "INVALID_LINE"]
+        );
+    }
+
+    #[test]
+    fn test_port_mappings() {
+        for valid in [
+            "80",
+            "8080:80",
+            "0.0.0.0:8080:80",
+            "127.0.0.1::80",
+            "[::1]:8080:80",
+            "53:53/udp",
+            "8000-8010:8000-8010",
+        ] {
+            assert!(is_valid_port_mapping(valid), "{valid} should be valid");
+        }
+        for invalid in ["", "http", "80:http", "80/icmp", "70000"] {
+            assert!(
+                !is_valid_port_mapping(invalid),
+                "{invalid} should be invalid"
+            );
+        }
+    }
+
+    #[test]
+    fn test_every_type_has_schema() {
+        for kind in ALL_TYPES {
+            assert!(!keys_for(*kind).is_empty());
+        }
+    }
+}
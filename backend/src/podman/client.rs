@@ -0,0 +1,277 @@
+use axum::{
+    body::Bytes,
+    http::{Request, StatusCode, header::HOST},
+};
+use http_body_util::{BodyExt, Empty};
+use hyper_util::rt::TokioIo;
+use serde_json::Value;
+use std::{path::PathBuf, time::Duration};
+use tokio::net::UnixStream;
+use tracing::warn;
+
+use crate::models::{AppError, ErrorCode};
+
+/// Prefijo de la API de libpod; Podman 4 y 5 aceptan esta versión
+pub const API_PREFIX: &str = "/v4.0.0/libpod";
+
+/// Tiempo máximo para una petición completa a la API, desde la conexión
+/// hasta leer la respuesta
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Objetos de Podman que se pueden listar
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resource {
+    Containers,
+    Images,
+    Volumes,
+    Networks,
+    Pods,
+}
+
+impl Resource {
+    /// Ruta de la API que los lista, sin el prefijo de versión
+    pub fn path(&self) -> &'static str {
+        match self {
+            // Sin `all` solo se listan los que están en marcha
+            Resource::Containers => "/containers/json?all=true",
+            Resource::Images => "/images/json",
+            Resource::Volumes => "/volumes/json",
+            Resource::Networks => "/networks/json",
+            Resource::Pods => "/pods/json",
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Resource::Containers => "containers",
+            Resource::Images => "images",
+            Resource::Volumes => "volumes",
+            Resource::Networks => "networks",
+            Resource::Pods => "pods",
+        }
+    }
+}
+
+/// Socket de la API de Podman del usuario (`$XDG_RUNTIME_DIR/podman/podman.sock`).
+///
+/// Sin `XDG_RUNTIME_DIR` se usa el socket del sistema.
+pub fn default_socket(runtime_dir: Option<&str>) -> PathBuf {
+    runtime_dir
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("/run"))
+        .join("podman/podman.sock")
+}
+
+/// Cliente de la API REST de Podman sobre su socket Unix
+#[derive(Debug, Clone)]
+pub struct Podman {
+    socket: PathBuf,
+    timeout: Duration,
+}
+
+impl Default for Podman {
+    fn default() -> Self {
+        Self::new(default_socket(None))
+    }
+}
+
+impl Podman {
+    pub fn new(socket: impl Into<PathBuf>) -> Self {
+        Self {
+            socket: socket.into(),
+            timeout: REQUEST_TIMEOUT,
+        }
+    }
+
+    #[cfg(test)]
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Lista los objetos de un tipo, tal y como los devuelve Podman
+    pub async fn list(&self, resource: Resource) -> Result<Vec<Value>, AppError> {
+        match self.get(resource.path()).await? {
+            Value::Array(objects) => Ok(objects),
+            // Podman devuelve `null` en lugar de una lista vacía en algunas
+            // versiones
+            Value::Null => Ok(Vec::new()),
+            _ => Err(AppError::new(
+                ErrorCode::PodmanRequestFailed,
+                format!("Unexpected response listing {}", resource.as_str()),
+            )),
+        }
+    }
+
+    /// Hace una petición GET a la API y devuelve el JSON de la respuesta.
+    ///
+    /// Se abre una conexión por petición: el socket es local y así no hay
+    /// que mantener conexiones entre peticiones. Si Podman no responde a
+    /// tiempo se considera que no está disponible.
+    async fn get(&self, path: &str) -> Result<Value, AppError> {
+        tokio::time::timeout(self.timeout, self.request(path))
+            .await
+            .unwrap_or_else(|_| {
+                Err(AppError::new(
+                    ErrorCode::PodmanUnavailable,
+                    format!(
+                        "Podman did not answer {} within {} seconds",
+                        path,
+                        self.timeout.as_secs_f32()
+                    ),
+                ))
+            })
+    }
+
+    async fn request(&self, path: &str) -> Result<Value, AppError> {
+        let unavailable = |e: &dyn std::fmt::Display| {
+            AppError::new(
+                ErrorCode::PodmanUnavailable,
+                format!("Failed to connect to {}: {}", self.socket.display(), e),
+            )
+        };
+        let stream = UnixStream::connect(&self.socket)
+            .await
+            .map_err(|e| unavailable(&e))?;
+        let (mut sender, connection) = hyper::client::conn::http1::handshake(TokioIo::new(stream))
+            .await
+            .map_err(|e| unavailable(&e))?;
+        tokio::spawn(async move {
+            if let Err(e) = connection.await {
+                warn!("Podman connection error: {}", e);
+            }
+        });
+
+        let request = Request::get(format!("{}{}", API_PREFIX, path))
+            .header(HOST, "d")
+            .body(Empty::<Bytes>::new())
+            .map_err(|e| AppError::new(ErrorCode::InternalError, e.to_string()))?;
+        let response = sender
+            .send_request(request)
+            .await
+            .map_err(|e| unavailable(&e))?;
+        let status = response.status();
+        let body = response
+            .into_body()
+            .collect()
+            .await
+            .map_err(|e| unavailable(&e))?
+            .to_bytes();
+        if status != StatusCode::OK {
+            // El cuerpo de los errores no siempre es JSON
+            let message = serde_json::from_slice::<Value>(&body)
+                .ok()
+                .and_then(|json| json["message"].as_str().map(String::from))
+                .unwrap_or_else(|| String::from_utf8_lossy(&body).trim().to_string());
+            return Err(AppError::new(
+                ErrorCode::PodmanRequestFailed,
+                format!("Podman request {} failed ({}): {}", path, status, message),
+            ));
+        }
+        serde_json::from_slice(&body).map_err(|e| {
+            AppError::new(
+                ErrorCode::PodmanRequestFailed,
+                format!("Invalid response from Podman: {}", e),
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::podman::PodmanStub;
+    use serde_json::json;
+
+    #[test]
+    fn test_default_socket() {
+        assert_eq!(
+            default_socket(Some("/run/user/1000")),
+            PathBuf::from("/run/user/1000/podman/podman.sock")
+        );
+        assert_eq!(
+            default_socket(Some("")),
+            PathBuf::from("/run/podman/podman.sock")
+        );
+        assert_eq!(
+            default_socket(None),
+            PathBuf::from("/run/podman/podman.sock")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_list_with_stub() {
+        let dir = tempfile::tempdir().unwrap();
+        let stub = PodmanStub::new();
+        stub.set(
+            "/containers/json",
+            json!([{"Id": "abc", "Names": ["systemd-web"]}]),
+        );
+        stub.set("/pods/json", Value::Null);
+        let podman = stub.serve(&dir.path().join("podman.sock"));
+
+        let containers = podman.list(Resource::Containers).await.unwrap();
+        assert_eq!(containers, [json!({"Id": "abc", "Names": ["systemd-web"]})]);
+        assert!(podman.list(Resource::Pods).await.unwrap().is_empty());
+        assert_eq!(
+            stub.requests()[0],
+            "/v4.0.0/libpod/containers/json?all=true"
+        );
+
+        let error = podman.list(Resource::Images).await.unwrap_err();
+        assert_eq!(error.code, ErrorCode::PodmanRequestFailed);
+        assert!(error.message.contains("404"), "{}", error.message);
+    }
+
+    #[tokio::test]
+    async fn test_list_without_socket() {
+        let dir = tempfile::tempdir().unwrap();
+        let error = Podman::new(dir.path().join("missing.sock"))
+            .list(Resource::Containers)
+            .await
+            .unwrap_err();
+        assert_eq!(error.code, ErrorCode::PodmanUnavailable);
+    }
+
+    #[tokio::test]
+    async fn test_list_times_out() {
+        let dir = tempfile::tempdir().unwrap();
+        let socket = dir.path().join("podman.sock");
+        // Acepta conexiones pero nunca responde
+        let _listener = tokio::net::UnixListener::bind(&socket).unwrap();
+
+        let error = Podman::new(&socket)
+            .with_timeout(Duration::from_millis(100))
+            .list(Resource::Containers)
+            .await
+            .unwrap_err();
+        assert_eq!(error.code, ErrorCode::PodmanUnavailable);
+    }
+
+    #[tokio::test]
+    async fn test_error_without_json_body() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let dir = tempfile::tempdir().unwrap();
+        let socket = dir.path().join("podman.sock");
+        let listener = tokio::net::UnixListener::bind(&socket).unwrap();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buffer = [0; 1024];
+            let _ = stream.read(&mut buffer).await.unwrap();
+            stream
+                .write_all(b"HTTP/1.1 500 Internal Server Error\r\nContent-Length: 5\r\n\r\nboom\n")
+                .await
+                .unwrap();
+        });
+
+        let error = Podman::new(&socket)
+            .list(Resource::Containers)
+            .await
+            .unwrap_err();
+        assert_eq!(error.code, ErrorCode::PodmanRequestFailed);
+        assert!(error.message.contains("500"), "{}", error.message);
+        assert!(error.message.contains("boom"), "{}", error.message);
+    }
+}
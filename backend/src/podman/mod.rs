@@ -0,0 +1,91 @@
+mod client;
+#[cfg(test)]
+mod stub;
+
+pub use client::{Podman, Resource, default_socket};
+#[cfg(test)]
+pub use stub::PodmanStub;
+
+use serde_json::{Value, json};
+use std::collections::HashMap;
+
+use crate::models::Quadlet;
+
+/// Etiqueta con la unidad de systemd desde la que se ha creado un objeto
+pub const SYSTEMD_UNIT_LABEL: &str = "PODMAN_SYSTEMD_UNIT";
+
+/// Añade a cada objeto de Podman el Quadlet que lo ha creado, en `quadlet`,
+/// según su etiqueta `PODMAN_SYSTEMD_UNIT`; queda a `null` si no hay
+/// ninguno.
+///
+/// Solo se tienen en cuenta los quadlets que no están ocultos, que son los
+/// que generan las unidades.
+pub fn link_quadlets(objects: &mut [Value], quadlets: &[Quadlet]) {
+    let units: HashMap<String, &Quadlet> = quadlets
+        .iter()
+        .filter(|q| !q.shadowed)
+        .map(|q| (q.unit_name(), q))
+        .collect();
+    for object in objects.iter_mut() {
+        let quadlet = unit_label(object)
+            .and_then(|unit| units.get(unit))
+            .map(|q| {
+                json!({
+                    "name": q.relative_path,
+                    "source": q.source,
+                    "unit": q.unit_name(),
+                })
+            })
+            .unwrap_or(Value::Null);
+        if let Value::Object(object) = object {
+            object.insert("quadlet".to_string(), quadlet);
+        }
+    }
+}
+
+/// Valor de la etiqueta `PODMAN_SYSTEMD_UNIT`. Las redes usan `labels` en
+/// minúsculas y el resto `Labels`.
+fn unit_label(object: &Value) -> Option<&str> {
+    ["Labels", "labels"]
+        .iter()
+        .find_map(|key| object.get(key)?.get(SYSTEMD_UNIT_LABEL)?.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::QuadletType;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_link_quadlets() {
+        let mut web = Quadlet::new(
+            "web".to_string(),
+            QuadletType::Container,
+            "[Container]\nImage=nginx\n".to_string(),
+            PathBuf::from("/q/apps/web.container"),
+        );
+        web.relative_path = "apps/web.container".to_string();
+        let mut network = Quadlet::new(
+            "app".to_string(),
+            QuadletType::Network,
+            "[Network]\n".to_string(),
+            PathBuf::from("/q/app.network"),
+        );
+        network.relative_path = "app.network".to_string();
+        network.shadowed = true;
+
+        let mut objects = vec![
+            json!({"Id": "1", "Labels": {"PODMAN_SYSTEMD_UNIT": "web.service"}}),
+            json!({"name": "app", "labels": {"PODMAN_SYSTEMD_UNIT": "app-network.service"}}),
+            json!({"Id": "2", "Labels": null}),
+        ];
+        link_quadlets(&mut objects, &[web, network]);
+        assert_eq!(
+            objects[0]["quadlet"],
+            json!({"name": "apps/web.container", "source": "user", "unit": "web.service"})
+        );
+        assert_eq!(objects[1]["quadlet"], Value::Null);
+        assert_eq!(objects[2]["quadlet"], Value::Null);
+    }
+}
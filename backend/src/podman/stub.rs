@@ -0,0 +1,76 @@
+use axum::{
+    Json, Router,
+    extract::State,
+    http::{StatusCode, Uri},
+    response::{IntoResponse, Response},
+};
+use serde_json::{Value, json};
+use std::{
+    collections::HashMap,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+use tokio::net::UnixListener;
+
+use super::{Podman, client::API_PREFIX};
+
+#[derive(Debug, Default)]
+struct StubState {
+    responses: HashMap<String, Value>,
+    requests: Vec<String>,
+}
+
+/// Servidor de prueba que imita la API de Podman en un socket Unix.
+///
+/// Responde con el JSON configurado para cada ruta y con un 404 como el de
+/// Podman para el resto.
+#[derive(Debug, Clone, Default)]
+pub struct PodmanStub {
+    state: Arc<Mutex<StubState>>,
+}
+
+impl PodmanStub {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Arranca el servidor en `socket` y devuelve un cliente conectado a él
+    pub fn serve(&self, socket: &Path) -> Podman {
+        let listener = UnixListener::bind(socket).unwrap();
+        let app = Router::new()
+            .fallback(respond)
+            .with_state(self.state.clone());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        Podman::new(socket)
+    }
+
+    /// Respuesta para una ruta de la API, sin prefijo de versión ni
+    /// parámetros (`/containers/json`)
+    pub fn set(&self, path: &str, response: Value) {
+        let mut state = self.state.lock().unwrap();
+        state.responses.insert(path.to_string(), response);
+    }
+
+    /// Peticiones recibidas, con su ruta completa
+    pub fn requests(&self) -> Vec<String> {
+        self.state.lock().unwrap().requests.clone()
+    }
+}
+
+async fn respond(State(state): State<Arc<Mutex<StubState>>>, uri: Uri) -> Response {
+    let mut state = state.lock().unwrap();
+    state.requests.push(uri.to_string());
+    let path = uri.path().strip_prefix(API_PREFIX).unwrap_or(uri.path());
+    match state.responses.get(path) {
+        Some(response) => Json(response.clone()).into_response(),
+        None => (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "cause": "no such object",
+                "message": format!("no such object: {}", path),
+                "response": 404,
+            })),
+        )
+            .into_response(),
+    }
+}
@@ -0,0 +1,191 @@
+use std::{
+    fs::{self, File},
+    io::{self, Write},
+    os::unix::fs::{MetadataExt, PermissionsExt},
+    path::{Path, PathBuf},
+};
+
+/// Extensión de la copia de seguridad; Podman ignora estos archivos
+const BACKUP_EXTENSION: &str = "bak";
+
+/// Escribe `content` en `path` de forma atómica.
+///
+/// El contenido se escribe en un archivo temporal del mismo directorio, se
+/// sincroniza con disco y después se renombra sobre el destino, de modo que
+/// nunca queda un archivo a medio escribir. Si el destino ya existe se
+/// conservan sus permisos y propietario, y si es un enlace simbólico se
+/// escribe en el archivo al que apunta. Un archivo nuevo lleva los permisos
+/// que deja la umask, como con `fs::write`.
+pub fn write_atomic(path: &Path, content: &[u8]) -> io::Result<()> {
+    let target = resolve(path)?;
+    let dir = target
+        .parent()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "path has no parent"))?;
+    let file_name = target
+        .file_name()
+        .and_then(|n| n.to_str())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "invalid file name"))?;
+    let previous = fs::metadata(&target).ok();
+
+    // Oculto, con una extensión que el generador no reconoce y un nombre
+    // único, para que dos escrituras a la vez no se pisen. Si algo falla el
+    // temporal se borra al descartarlo. Se crea con 0666 para que el sistema
+    // aplique la umask, en lugar del 0600 de `tempfile`.
+    let mut file = tempfile::Builder::new()
+        .prefix(&format!(".{}.", file_name))
+        .suffix(".tmp")
+        .permissions(fs::Permissions::from_mode(0o666))
+        .tempfile_in(dir)?;
+    file.write_all(content)?;
+    if let Some(previous) = &previous {
+        file.as_file().set_permissions(fs::Permissions::from_mode(previous.mode()))?;
+        preserve_owner(file.path(), previous)?;
+    }
+    file.as_file().sync_all()?;
+    file.persist(&target).map_err(|e| e.error)?;
+    // Sincronizar el directorio para que el renombrado sobreviva a un corte
+    File::open(dir)?.sync_all()
+}
+
+/// Guarda una copia del contenido actual de `path` en `path.bak`.
+///
+/// Devuelve la ruta de la copia, o `None` si el archivo todavía no existe.
+pub fn backup(path: &Path) -> io::Result<Option<PathBuf>> {
+    let target = resolve(path)?;
+    let content = match fs::read(&target) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    let backup = backup_path(&target);
+    write_atomic(&backup, &content)?;
+    // La copia lleva los mismos permisos que el original
+    let metadata = fs::metadata(&target)?;
+    fs::set_permissions(&backup, fs::Permissions::from_mode(metadata.mode()))?;
+    Ok(Some(backup))
+}
+
+/// Ruta de la copia de seguridad de un archivo (`web.container.bak`)
+pub fn backup_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".");
+    name.push(BACKUP_EXTENSION);
+    path.with_file_name(name)
+}
+
+/// Sigue el enlace simbólico si el destino ya existe
+fn resolve(path: &Path) -> io::Result<PathBuf> {
+    match fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.file_type().is_symlink() => fs::canonicalize(path),
+        _ => Ok(path.to_path_buf()),
+    }
+}
+
+/// Copia el propietario del archivo original.
+///
+/// Un usuario sin privilegios no puede cambiar el propietario; en ese caso
+/// el archivo queda a su nombre, como con una escritura normal.
+fn preserve_owner(path: &Path, previous: &fs::Metadata) -> io::Result<()> {
+    let current = fs::metadata(path)?;
+    if current.uid() == previous.uid() && current.gid() == previous.gid() {
+        return Ok(());
+    }
+    match std::os::unix::fs::chown(path, Some(previous.uid()), Some(previous.gid())) {
+        Err(e) if e.kind() == io::ErrorKind::PermissionDenied => Ok(()),
+        result => result,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Permisos de un archivo nuevo con la umask del proceso
+    fn default_mode(dir: &Path) -> u32 {
+        let path = dir.join("umask");
+        fs::write(&path, "").unwrap();
+        let mode = fs::metadata(&path).unwrap().mode() & 0o777;
+        fs::remove_file(path).unwrap();
+        mode
+    }
+
+    #[test]
+    fn test_write_atomic_new_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("web.container");
+        write_atomic(&path, b"[Container]\nImage=nginx\n").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "[Container]\nImage=nginx\n");
+        assert_eq!(fs::metadata(&path).unwrap().mode() & 0o777, default_mode(dir.path()));
+        // No quedan temporales
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_write_atomic_preserves_mode() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("web.container");
+        fs::write(&path, "old").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o600)).unwrap();
+
+        write_atomic(&path, b"new").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+        assert_eq!(fs::metadata(&path).unwrap().mode() & 0o777, 0o600);
+    }
+
+    #[test]
+    fn test_write_atomic_through_symlink() {
+        let dir = tempfile::tempdir().unwrap();
+        let real = dir.path().join("real.container");
+        let link = dir.path().join("web.container");
+        fs::write(&real, "old").unwrap();
+        std::os::unix::fs::symlink(&real, &link).unwrap();
+
+        write_atomic(&link, b"new").unwrap();
+        assert!(fs::symlink_metadata(&link).unwrap().file_type().is_symlink());
+        assert_eq!(fs::read_to_string(&real).unwrap(), "new");
+    }
+
+    #[test]
+    fn test_write_atomic_concurrent_writes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("web.container");
+        // Un temporal con el nombre antiguo, como el que deja un proceso caído
+        fs::write(dir.path().join(format!(".web.container.{}.tmp", std::process::id())), "").unwrap();
+
+        std::thread::scope(|scope| {
+            for content in ["a", "b", "c", "d"] {
+                let path = &path;
+                scope.spawn(move || {
+                    for _ in 0..25 {
+                        write_atomic(path, content.as_bytes()).unwrap();
+                    }
+                });
+            }
+        });
+        assert_eq!(fs::read_to_string(&path).unwrap().len(), 1);
+        assert_eq!(fs::metadata(&path).unwrap().mode() & 0o777, default_mode(dir.path()));
+        // Solo quedan el destino y el temporal antiguo
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn test_write_atomic_missing_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("missing/web.container");
+        assert!(write_atomic(&path, b"new").is_err());
+    }
+
+    #[test]
+    fn test_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("web.container");
+        assert_eq!(backup(&path).unwrap(), None);
+
+        fs::write(&path, "old").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o640)).unwrap();
+        let copy = backup(&path).unwrap().unwrap();
+        assert_eq!(copy, dir.path().join("web.container.bak"));
+        assert_eq!(fs::read_to_string(&copy).unwrap(), "old");
+        assert_eq!(fs::metadata(&copy).unwrap().mode() & 0o777, 0o640);
+    }
+}
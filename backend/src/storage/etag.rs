@@ -0,0 +1,74 @@
+use sha2::{Digest, Sha256};
+use std::{
+    fs, io,
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Calcula el ETag de un archivo a partir de su contenido y su fecha de
+/// modificación.
+///
+/// Incluir la fecha hace que también cambie si alguien reescribe el archivo
+/// con el mismo contenido por otra vía.
+pub fn etag(content: &[u8], modified: Option<SystemTime>) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(content);
+    if let Some(nanos) = modified
+        .and_then(|m| m.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_nanos())
+    {
+        hasher.update(nanos.to_le_bytes());
+    }
+    let digest = format!("{:x}", hasher.finalize());
+    format!("\"{}\"", &digest[..32])
+}
+
+/// ETag del archivo tal y como está ahora en disco
+pub fn file_etag(path: &Path) -> io::Result<String> {
+    let content = fs::read(path)?;
+    let modified = fs::metadata(path)?.modified().ok();
+    Ok(etag(&content, modified))
+}
+
+/// Comprueba si el valor de una cabecera `If-Match` (una lista de ETags o
+/// `*`) acepta `etag`
+pub fn etag_matches(if_match: &str, etag: &str) -> bool {
+    let etag = etag.trim_start_matches("W/");
+    if_match
+        .split(',')
+        .map(str::trim)
+        .any(|candidate| candidate == "*" || candidate.trim_start_matches("W/") == etag)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_etag() {
+        let time = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let tag = etag(b"[Container]\nImage=nginx\n", Some(time));
+        assert!(tag.starts_with('"') && tag.ends_with('"'));
+        assert_eq!(tag.len(), 34);
+        assert_eq!(tag, etag(b"[Container]\nImage=nginx\n", Some(time)));
+        assert_ne!(tag, etag(b"[Container]\nImage=alpine\n", Some(time)));
+        assert_ne!(
+            tag,
+            etag(
+                b"[Container]\nImage=nginx\n",
+                Some(time + Duration::from_nanos(1))
+            )
+        );
+    }
+
+    #[test]
+    fn test_etag_matches() {
+        assert!(etag_matches("\"abc\"", "\"abc\""));
+        assert!(etag_matches("\"xyz\", \"abc\"", "\"abc\""));
+        assert!(etag_matches("W/\"abc\"", "\"abc\""));
+        assert!(etag_matches("*", "\"abc\""));
+        assert!(!etag_matches("\"xyz\"", "\"abc\""));
+        assert!(!etag_matches("", "\"abc\""));
+    }
+}
@@ -0,0 +1,334 @@
+use serde::Serialize;
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+    sync::{Mutex, PoisonError},
+};
+
+/// Directorio del repositorio de revisiones, relativo al de quadlets.
+///
+/// Es un directorio oculto, así que no se lista ni lo recorre Podman, y no
+/// interfiere con un posible repositorio git del propio usuario.
+const HISTORY_DIR: &str = ".quma/history";
+
+/// Autor por defecto de las revisiones
+pub const DEFAULT_AUTHOR: &str = "quma";
+
+/// Los registros se hacen de uno en uno: dos `git add` o `git commit` a la
+/// vez sobre el mismo repositorio fallan por su `index.lock`
+static RECORD_LOCK: Mutex<()> = Mutex::new(());
+
+/// Revisión guardada de un archivo
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Revision {
+    /// Identificador de la revisión (hash del commit)
+    pub id: String,
+    pub author: String,
+    /// Fecha en formato RFC 3339
+    pub timestamp: String,
+    pub message: String,
+    /// Hash del contenido del archivo en esta revisión (blob de git)
+    pub content_hash: String,
+}
+
+/// Historial de revisiones de un directorio de quadlets.
+///
+/// Se guarda en un repositorio git local que se crea la primera vez que se
+/// registra una revisión.
+pub struct History {
+    root: PathBuf,
+    git_dir: PathBuf,
+}
+
+impl History {
+    /// Historial del directorio de quadlets `root`
+    pub fn new(root: &Path) -> Self {
+        Self {
+            root: root.to_path_buf(),
+            git_dir: root.join(HISTORY_DIR),
+        }
+    }
+
+    /// Registra el contenido actual de `paths` como una nueva revisión.
+    ///
+    /// Las rutas pueden ser archivos o directorios (de drop-ins) y también
+    /// se registran los que se han borrado. Si nada ha cambiado desde la
+    /// última revisión no se crea otra y se devuelve `None`.
+    pub fn record(
+        &self,
+        paths: &[&str],
+        author: &str,
+        message: &str,
+    ) -> Result<Option<Revision>, String> {
+        let _lock = RECORD_LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+        if !self.git_dir.exists() {
+            std::fs::create_dir_all(&self.git_dir)
+                .map_err(|e| format!("Failed to create history directory: {}", e))?;
+            self.git(&["init", "--quiet"])?;
+        }
+        // git rechaza rutas que no existen ni están registradas
+        let mut known = Vec::new();
+        for path in paths {
+            if self.root.join(path).exists() || !self.git(&["ls-files", "--", path])?.is_empty() {
+                known.push(*path);
+            }
+        }
+        let Some(first) = known.first().copied() else {
+            return Ok(None);
+        };
+        let mut args = vec!["add", "--all", "--"];
+        args.extend(&known);
+        self.git(&args)?;
+        let mut args = vec!["diff", "--cached", "--quiet", "--"];
+        args.extend(&known);
+        if self.git(&args).is_ok() {
+            return Ok(None);
+        }
+        let author = author.replace(['<', '>', '\n'], "");
+        let author = if author.trim().is_empty() {
+            DEFAULT_AUTHOR
+        } else {
+            author.trim()
+        };
+        let output = self
+            .command()
+            .args([
+                "commit",
+                "--quiet",
+                "--no-verify",
+                "--message",
+                message,
+                "--",
+            ])
+            .args(&known)
+            .env("GIT_AUTHOR_NAME", author)
+            .env("GIT_AUTHOR_EMAIL", "quma@localhost")
+            .env("GIT_COMMITTER_NAME", "QuMa")
+            .env("GIT_COMMITTER_EMAIL", "quma@localhost")
+            .output()
+            .map_err(|e| format!("Failed to execute git: {}", e))?;
+        if !output.status.success() {
+            return Err(format!(
+                "git commit failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        Ok(self.revisions(first)?.into_iter().next())
+    }
+
+    /// Revisiones de `relative`, de la más reciente a la más antigua
+    pub fn revisions(&self, relative: &str) -> Result<Vec<Revision>, String> {
+        if !self.git_dir.exists() {
+            return Ok(vec![]);
+        }
+        let log = self.git(&[
+            "log",
+            "--format=%x1e%H%x1f%an%x1f%aI%x1f%s",
+            "--raw",
+            "--no-abbrev",
+            "--",
+            relative,
+        ]);
+        // Un repositorio recién creado aún no tiene commits
+        let Ok(log) = log else {
+            return Ok(vec![]);
+        };
+        let mut revisions = Vec::new();
+        for record in log.split('\x1e').filter(|r| !r.trim().is_empty()) {
+            let mut lines = record.lines();
+            let header: Vec<&str> = lines.next().unwrap_or_default().split('\x1f').collect();
+            let [id, author, timestamp, message] = header[..] else {
+                continue;
+            };
+            // `:100644 100644 <antes> <después> M\truta`
+            let content_hash = lines
+                .filter_map(|line| line.split_whitespace().nth(3))
+                .next()
+                .unwrap_or_default();
+            revisions.push(Revision {
+                id: id.to_string(),
+                author: author.to_string(),
+                timestamp: timestamp.to_string(),
+                message: message.to_string(),
+                content_hash: content_hash.to_string(),
+            });
+        }
+        Ok(revisions)
+    }
+
+    /// Contenido de `relative` en la revisión `id`
+    pub fn show(&self, relative: &str, id: &str) -> Result<String, String> {
+        check_revision(id)?;
+        self.git(&["show", &format!("{}:{}", id, relative)])
+    }
+
+    /// Diff unificado de `relative` entre dos revisiones, o entre una
+    /// revisión y el contenido actual si `to` es `None`
+    pub fn diff(&self, relative: &str, from: &str, to: Option<&str>) -> Result<String, String> {
+        check_revision(from)?;
+        let mut args = vec!["diff", "--no-color", "--no-ext-diff", from];
+        if let Some(to) = to {
+            check_revision(to)?;
+            args.push(to);
+        }
+        args.extend(["--", relative]);
+        self.git(&args)
+    }
+
+    /// Comando git sobre el repositorio de revisiones.
+    ///
+    /// Se ignora la configuración del sistema y del usuario para que no
+    /// afecten hooks, firmas o alias.
+    fn command(&self) -> Command {
+        let mut command = Command::new("git");
+        command
+            .arg(format!("--git-dir={}", self.git_dir.display()))
+            .arg(format!("--work-tree={}", self.root.display()))
+            .env("GIT_CONFIG_NOSYSTEM", "1")
+            .env("GIT_CONFIG_GLOBAL", "/dev/null");
+        command
+    }
+
+    /// Ejecuta git y devuelve la salida estándar
+    fn git(&self, args: &[&str]) -> Result<String, String> {
+        let output = self
+            .command()
+            .args(args)
+            .output()
+            .map_err(|e| format!("Failed to execute git: {}", e))?;
+        if !output.status.success() {
+            return Err(format!(
+                "git {} failed: {}",
+                args.first().unwrap_or(&""),
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+}
+
+/// Comprueba que un identificador de revisión sea un hash, para que no se
+/// pueda pasar a git como opción
+pub fn check_revision(id: &str) -> Result<(), String> {
+    if id.len() < 4 || id.len() > 64 || !id.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!("Invalid revision: {}", id));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_history_record_and_list() {
+        let root = tempfile::tempdir().unwrap();
+        let history = History::new(root.path());
+        assert!(history.revisions("web.container").unwrap().is_empty());
+
+        fs::write(
+            root.path().join("web.container"),
+            "[Container]\nImage=nginx:1.25\n",
+        )
+        .unwrap();
+        let first = history
+            .record(&["web.container"], "ana", "Create web")
+            .unwrap()
+            .unwrap();
+        assert_eq!(first.author, "ana");
+        assert_eq!(first.message, "Create web");
+        assert_eq!(first.content_hash.len(), 40);
+
+        // Sin cambios no hay revisión nueva
+        assert_eq!(
+            history
+                .record(&["web.container"], "ana", "Save web")
+                .unwrap(),
+            None
+        );
+
+        fs::write(
+            root.path().join("web.container"),
+            "[Container]\nImage=nginx:1.27\n",
+        )
+        .unwrap();
+        fs::write(
+            root.path().join("db.container"),
+            "[Container]\nImage=postgres\n",
+        )
+        .unwrap();
+        let second = history
+            .record(&["web.container"], "", "Update web")
+            .unwrap()
+            .unwrap();
+        assert_eq!(second.author, DEFAULT_AUTHOR);
+        assert_ne!(second.content_hash, first.content_hash);
+
+        let revisions = history.revisions("web.container").unwrap();
+        assert_eq!(revisions, vec![second.clone(), first.clone()]);
+        // Solo se registra el archivo indicado
+        assert!(history.revisions("db.container").unwrap().is_empty());
+
+        assert_eq!(
+            history.show("web.container", &first.id).unwrap(),
+            "[Container]\nImage=nginx:1.25\n"
+        );
+        let diff = history
+            .diff("web.container", &first.id, Some(&second.id))
+            .unwrap();
+        assert!(diff.contains("-Image=nginx:1.25\n+Image=nginx:1.27"));
+
+        fs::write(
+            root.path().join("web.container"),
+            "[Container]\nImage=nginx:latest\n",
+        )
+        .unwrap();
+        let diff = history.diff("web.container", &second.id, None).unwrap();
+        assert!(diff.contains("+Image=nginx:latest"));
+
+        // Los borrados también quedan registrados
+        fs::remove_file(root.path().join("web.container")).unwrap();
+        let deleted = history
+            .record(&["web.container", "web.container.d"], "ana", "Delete web")
+            .unwrap()
+            .unwrap();
+        assert_eq!(deleted.message, "Delete web");
+        assert_eq!(history.revisions("web.container").unwrap().len(), 3);
+        assert_eq!(
+            history
+                .record(&["missing.container"], "ana", "Nothing")
+                .unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_history_concurrent_records() {
+        let root = tempfile::tempdir().unwrap();
+        let history = History::new(root.path());
+        std::thread::scope(|scope| {
+            for i in 0..8 {
+                let history = &history;
+                let root = root.path();
+                scope.spawn(move || {
+                    let name = format!("web{}.container", i);
+                    std::fs::write(root.join(&name), "[Container]\nImage=nginx\n").unwrap();
+                    history.record(&[&name], "ana", "Save").unwrap().unwrap();
+                });
+            }
+        });
+        for i in 0..8 {
+            let name = format!("web{}.container", i);
+            assert_eq!(history.revisions(&name).unwrap().len(), 1, "{name}");
+        }
+    }
+
+    #[test]
+    fn test_check_revision() {
+        assert!(check_revision("49e44beb").is_ok());
+        assert!(check_revision("--output=/tmp/x").is_err());
+        assert!(check_revision("HEAD").is_err());
+        assert!(check_revision("abc").is_err());
+    }
+}
@@ -0,0 +1,9 @@
+mod atomic;
+mod etag;
+mod history;
+mod paths;
+
+pub use atomic::{backup, write_atomic};
+pub use etag::{etag, etag_matches, file_etag};
+pub use history::{DEFAULT_AUTHOR, History, check_revision};
+pub use paths::{check_dropin_name, check_relative_path, confine};
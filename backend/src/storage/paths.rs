@@ -0,0 +1,183 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::models::dropin_directory_type;
+
+/// Longitud máxima de un componente de ruta en la mayoría de sistemas de archivos
+const MAX_COMPONENT_LEN: usize = 255;
+
+/// Rechaza nombres que no sean un único componente de ruta
+pub fn check_file_name(name: &str) -> Result<(), String> {
+    if name.is_empty()
+        || name == "."
+        || name == ".."
+        || name.len() > MAX_COMPONENT_LEN
+        || name.contains(['/', '\\'])
+        || name.chars().any(char::is_control)
+    {
+        return Err(format!("Invalid file name: {}", name.escape_default()));
+    }
+    Ok(())
+}
+
+/// Valida una ruta relativa al directorio de quadlets (`web.container` o
+/// `apps/web.container`) y la devuelve normalizada.
+///
+/// Solo se admiten componentes normales: nada de rutas absolutas, `..`,
+/// carpetas ocultas ni directorios de drop-ins, para que el archivo quede
+/// dentro del directorio y sea visible al listar.
+pub fn check_relative_path(name: &str) -> Result<String, String> {
+    let components: Vec<&str> = name.split('/').collect();
+    let (file_name, folders) = components
+        .split_last()
+        .ok_or_else(|| format!("Invalid file name: {}", name))?;
+    check_file_name(file_name)?;
+    for folder in folders {
+        check_file_name(folder).map_err(|_| format!("Invalid path: {}", name.escape_default()))?;
+        if folder.starts_with('.') || dropin_directory_type(folder).is_some() {
+            return Err(format!("Invalid folder: {}", folder));
+        }
+    }
+    Ok(components.join("/"))
+}
+
+/// Comprueba que el nombre de un drop-in sea un archivo `.conf`
+pub fn check_dropin_name(name: &str) -> Result<(), String> {
+    check_file_name(name)?;
+    if !name.ends_with(".conf") || name == ".conf" {
+        return Err(format!("Invalid drop-in name: {} (expected a .conf file)", name));
+    }
+    Ok(())
+}
+
+/// Resuelve `relative` dentro de `root` garantizando que no se sale de él.
+///
+/// Cada componente debe ser un nombre normal, y los enlaces simbólicos que
+/// ya existan en el camino solo se aceptan si apuntan dentro de `root` (una
+/// vez canonicalizado). Los enlaces rotos se rechazan, porque escribir a
+/// través de ellos crearía el destino en cualquier parte. `root` tiene que
+/// existir.
+pub fn confine(root: &Path, relative: &str) -> Result<PathBuf, String> {
+    for component in relative.split('/') {
+        check_file_name(component).map_err(|_| format!("Invalid path: {}", relative.escape_default()))?;
+    }
+    let root = root
+        .canonicalize()
+        .map_err(|e| format!("Failed to resolve {}: {}", root.display(), e))?;
+
+    let mut path = root.clone();
+    for component in relative.split('/') {
+        path.push(component);
+        let Ok(metadata) = fs::symlink_metadata(&path) else {
+            // El resto del camino todavía no existe
+            break;
+        };
+        if metadata.file_type().is_symlink() {
+            let target = path
+                .canonicalize()
+                .map_err(|_| format!("Broken symlink in path: {}", relative))?;
+            if !target.starts_with(&root) {
+                return Err(format!("Path escapes the quadlet directory: {}", relative));
+            }
+        }
+    }
+    Ok(root.join(relative))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::symlink;
+
+    #[test]
+    fn test_check_relative_path() {
+        assert_eq!(check_relative_path("web.container").unwrap(), "web.container");
+        assert_eq!(check_relative_path("apps/web.container").unwrap(), "apps/web.container");
+        for invalid in [
+            "",
+            "/etc/web.container",
+            "../web.container",
+            "../../.bashrc.container",
+            "apps/../../web.container",
+            "apps//web.container",
+            "./web.container",
+            ".hidden/web.container",
+            "web.container.d/web.container",
+            "apps\\web.container",
+            "web\0.container",
+            "apps\n/web.container",
+        ] {
+            assert!(check_relative_path(invalid).is_err(), "{invalid:?} should be rejected");
+        }
+        let long = format!("{}.container", "a".repeat(MAX_COMPONENT_LEN));
+        assert!(check_relative_path(&long).is_err());
+    }
+
+    #[test]
+    fn test_check_dropin_name() {
+        assert!(check_dropin_name("10-env.conf").is_ok());
+        assert!(check_dropin_name("10-env.txt").is_err());
+        assert!(check_dropin_name("../10-env.conf").is_err());
+        assert!(check_dropin_name(".conf").is_err());
+        assert!(check_file_name("..").is_err());
+    }
+
+    #[test]
+    fn test_confine_plain_paths() {
+        let root = tempfile::tempdir().unwrap();
+        let canonical = root.path().canonicalize().unwrap();
+        assert_eq!(
+            confine(root.path(), "web.container").unwrap(),
+            canonical.join("web.container")
+        );
+        assert_eq!(
+            confine(root.path(), "apps/new/web.container").unwrap(),
+            canonical.join("apps/new/web.container")
+        );
+        assert!(confine(root.path(), "../web.container").is_err());
+        assert!(confine(root.path(), "/etc/passwd").is_err());
+        assert!(confine(&root.path().join("missing"), "web.container").is_err());
+    }
+
+    #[test]
+    fn test_confine_rejects_symlinks_outside_root() {
+        let outside = tempfile::tempdir().unwrap();
+        let root = tempfile::tempdir().unwrap();
+        symlink(outside.path(), root.path().join("apps")).unwrap();
+        symlink(outside.path().join(".bashrc"), root.path().join("web.container")).unwrap();
+        fs::write(outside.path().join(".bashrc"), "").unwrap();
+        symlink(outside.path().join("missing"), root.path().join("db.container")).unwrap();
+
+        assert!(confine(root.path(), "apps/web.container").is_err());
+        assert!(confine(root.path(), "web.container").is_err());
+        assert!(confine(root.path(), "db.container").is_err());
+    }
+
+    #[test]
+    fn test_confine_allows_symlinks_inside_root() {
+        let root = tempfile::tempdir().unwrap();
+        fs::create_dir(root.path().join("real")).unwrap();
+        fs::write(root.path().join("real/web.container"), "").unwrap();
+        symlink(root.path().join("real"), root.path().join("apps")).unwrap();
+        symlink(root.path().join("real/web.container"), root.path().join("web.container")).unwrap();
+
+        assert!(confine(root.path(), "apps/web.container").is_ok());
+        assert!(confine(root.path(), "web.container").is_ok());
+    }
+
+    #[test]
+    fn test_confine_symlinked_root() {
+        // La raíz puede ser un enlace (p. ej. ~/.config gestionado con stow)
+        let real = tempfile::tempdir().unwrap();
+        let links = tempfile::tempdir().unwrap();
+        let root = links.path().join("systemd");
+        symlink(real.path(), &root).unwrap();
+
+        assert_eq!(
+            confine(&root, "web.container").unwrap(),
+            real.path().canonicalize().unwrap().join("web.container")
+        );
+    }
+}
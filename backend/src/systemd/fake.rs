@@ -0,0 +1,262 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Mutex,
+};
+use tokio::sync::mpsc;
+use tokio_stream::{StreamExt, iter, wrappers::UnboundedReceiverStream};
+
+use super::{LogStream, SystemdManager};
+use crate::models::{AppError, ErrorCode, JournalQuery, UnitState};
+
+/// systemd en memoria para las pruebas.
+///
+/// Registra cada llamada (`daemon-reload`, `start web.service`...) y permite
+/// preparar el estado de las unidades y hacer fallar comandos concretos.
+#[derive(Debug, Default)]
+pub struct FakeSystemd {
+    state: Mutex<FakeState>,
+}
+
+#[derive(Debug, Default)]
+struct FakeState {
+    units: HashMap<String, UnitState>,
+    logs: HashMap<String, Vec<String>>,
+    calls: Vec<String>,
+    failing: HashSet<String>,
+    crashing: HashSet<String>,
+    /// Clientes que siguen el journal de cada unidad
+    followers: HashMap<String, Vec<mpsc::UnboundedSender<String>>>,
+}
+
+impl FakeState {
+    /// Registra la llamada y devuelve un error si se ha pedido que falle
+    fn call(&mut self, command: String) -> Result<(), AppError> {
+        let failed = self.failing.contains(&command);
+        self.calls.push(command.clone());
+        if failed {
+            let code = if command == "daemon-reload" {
+                ErrorCode::SystemdReloadFailed
+            } else {
+                ErrorCode::SystemdCommandFailed
+            };
+            return Err(AppError::new(code, format!("systemctl {} failed", command)));
+        }
+        Ok(())
+    }
+}
+
+impl FakeSystemd {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Fija el estado de una unidad; `generated` se deduce de `load_state`
+    pub fn set_unit(&self, mut state: UnitState) {
+        state.generated = state.load_state != "not-found";
+        let mut fake = self.state.lock().unwrap();
+        fake.units.insert(state.unit.clone(), state);
+    }
+
+    /// Añade una línea al journal de una unidad y se la envía a quienes lo
+    /// siguen
+    pub fn push_log(&self, unit: &str, line: &str) {
+        let mut fake = self.state.lock().unwrap();
+        if let Some(followers) = fake.followers.get_mut(unit) {
+            followers.retain(|follower| follower.send(line.to_string()).is_ok());
+        }
+        fake.logs
+            .entry(unit.to_string())
+            .or_default()
+            .push(line.to_string());
+    }
+
+    /// Hace fallar un comando tal y como aparece en [`FakeSystemd::calls`],
+    /// p. ej. `daemon-reload` o `restart web.service`
+    pub fn fail(&self, command: &str) {
+        self.state
+            .lock()
+            .unwrap()
+            .failing
+            .insert(command.to_string());
+    }
+
+    /// Hace que la unidad acabe en `failed` cada vez que se arranca
+    pub fn crash(&self, unit: &str) {
+        self.state.lock().unwrap().crashing.insert(unit.to_string());
+    }
+
+    /// Comandos recibidos hasta ahora, en orden
+    pub fn calls(&self) -> Vec<String> {
+        self.state.lock().unwrap().calls.clone()
+    }
+
+    /// Cambia el estado activo de una unidad tras `start`, `stop` o `restart`
+    fn transition(&self, verb: &str, unit: &str, active: bool) -> Result<(), AppError> {
+        let mut fake = self.state.lock().unwrap();
+        fake.call(format!("{} {}", verb, unit))?;
+        let crashed = active && fake.crashing.contains(unit);
+        let state = fake
+            .units
+            .entry(unit.to_string())
+            .or_insert_with(|| unknown(unit));
+        state.load_state = "loaded".to_string();
+        state.generated = true;
+        (state.active_state, state.sub_state) = if crashed {
+            ("failed".to_string(), "failed".to_string())
+        } else if active {
+            ("active".to_string(), "running".to_string())
+        } else {
+            ("inactive".to_string(), "dead".to_string())
+        };
+        Ok(())
+    }
+}
+
+/// Estado de una unidad que systemd no conoce
+fn unknown(unit: &str) -> UnitState {
+    UnitState {
+        unit: unit.to_string(),
+        load_state: "not-found".to_string(),
+        active_state: "inactive".to_string(),
+        sub_state: "dead".to_string(),
+        ..Default::default()
+    }
+}
+
+impl SystemdManager for FakeSystemd {
+    fn daemon_reload(&self) -> Result<(), AppError> {
+        self.state.lock().unwrap().call("daemon-reload".to_string())
+    }
+
+    fn start(&self, unit: &str) -> Result<(), AppError> {
+        self.transition("start", unit, true)
+    }
+
+    fn stop(&self, unit: &str) -> Result<(), AppError> {
+        self.transition("stop", unit, false)
+    }
+
+    fn restart(&self, unit: &str) -> Result<(), AppError> {
+        self.transition("restart", unit, true)
+    }
+
+    fn reload(&self, unit: &str) -> Result<(), AppError> {
+        self.state.lock().unwrap().call(format!("reload {}", unit))
+    }
+
+    fn disable(&self, unit: &str) -> Result<(), AppError> {
+        let mut fake = self.state.lock().unwrap();
+        fake.call(format!("disable {}", unit))?;
+        let state = fake
+            .units
+            .entry(unit.to_string())
+            .or_insert_with(|| unknown(unit));
+        state.unit_file_state = "disabled".to_string();
+        Ok(())
+    }
+
+    fn status(&self, unit: &str) -> Result<UnitState, AppError> {
+        let fake = self.state.lock().unwrap();
+        Ok(fake
+            .units
+            .get(unit)
+            .cloned()
+            .unwrap_or_else(|| unknown(unit)))
+    }
+
+    /// Solo tiene en cuenta `lines`; el resto de filtros los aplica
+    /// `journalctl`
+    fn logs(&self, unit: &str, query: &JournalQuery) -> Result<Vec<String>, AppError> {
+        let fake = self.state.lock().unwrap();
+        let logs = fake.logs.get(unit).map(Vec::as_slice).unwrap_or_default();
+        let lines = query.lines_or_default();
+        Ok(logs[logs.len().saturating_sub(lines)..].to_vec())
+    }
+
+    fn follow(&self, unit: &str, query: &JournalQuery) -> Result<LogStream, AppError> {
+        let current = self.logs(unit, query)?;
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.state
+            .lock()
+            .unwrap()
+            .followers
+            .entry(unit.to_string())
+            .or_default()
+            .push(tx);
+        Ok(Box::pin(
+            iter(current).chain(UnboundedReceiverStream::new(rx)),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fake_systemd() {
+        let systemd = FakeSystemd::new();
+        assert_eq!(
+            systemd.status("web.service").unwrap().load_state,
+            "not-found"
+        );
+
+        systemd.start("web.service").unwrap();
+        let state = systemd.status("web.service").unwrap();
+        assert!(state.is_active());
+        assert_eq!(state.sub_state, "running");
+        systemd.stop("web.service").unwrap();
+        assert!(!systemd.status("web.service").unwrap().is_active());
+        systemd.crash("web.service");
+        systemd.restart("web.service").unwrap();
+        assert_eq!(
+            systemd.status("web.service").unwrap().active_state,
+            "failed"
+        );
+
+        systemd.fail("daemon-reload");
+        let error = systemd.daemon_reload().unwrap_err();
+        assert_eq!(error.code, ErrorCode::SystemdReloadFailed);
+        assert_eq!(
+            systemd.calls(),
+            [
+                "start web.service",
+                "stop web.service",
+                "restart web.service",
+                "daemon-reload"
+            ]
+        );
+
+        for line in ["one", "two", "three"] {
+            systemd.push_log("web.service", line);
+        }
+        assert_eq!(
+            systemd.logs("web.service", &JournalQuery::last(2)).unwrap(),
+            ["two", "three"]
+        );
+        assert!(
+            systemd
+                .logs("db.service", &JournalQuery::default())
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fake_systemd_follow() {
+        let systemd = FakeSystemd::new();
+        systemd.push_log("web.service", "old");
+        let mut stream = systemd
+            .follow("web.service", &JournalQuery::default())
+            .unwrap();
+        systemd.push_log("web.service", "new");
+        systemd.push_log("db.service", "other");
+
+        assert_eq!(stream.next().await.unwrap(), "old");
+        assert_eq!(stream.next().await.unwrap(), "new");
+        drop(stream);
+        // Los clientes que se han ido se descartan al enviar
+        systemd.push_log("web.service", "later");
+        assert!(systemd.state.lock().unwrap().followers["web.service"].is_empty());
+    }
+}
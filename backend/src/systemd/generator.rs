@@ -0,0 +1,173 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use crate::models::{AppError, ErrorCode};
+
+/// Ruta habitual del generador de Quadlet de Podman
+pub const DEFAULT_GENERATOR: &str = "/usr/libexec/podman/quadlet";
+
+/// Generador de Quadlet de Podman, que convierte los archivos Quadlet en
+/// unidades de systemd
+#[derive(Debug, Clone)]
+pub struct Generator {
+    path: PathBuf,
+}
+
+/// Resultado de ejecutar el generador en modo de prueba
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DryRun {
+    /// Unidades generadas, por nombre (`web.service`)
+    pub units: BTreeMap<String, String>,
+    /// Mensajes del generador (errores y avisos), uno por línea
+    pub messages: Vec<String>,
+}
+
+impl Default for Generator {
+    fn default() -> Self {
+        Self::new(DEFAULT_GENERATOR)
+    }
+}
+
+impl Generator {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Ejecuta `quadlet -dryrun -user` sobre los archivos de `dir`.
+    ///
+    /// El generador no escribe nada: imprime cada unidad precedida de una
+    /// línea `---web.service---`. Si falla al convertir algún archivo sale
+    /// con error, pero el resto de unidades se generan igualmente, así que
+    /// solo se devuelve un error si no se ha podido ejecutar.
+    pub fn dry_run(&self, dir: &Path) -> Result<DryRun, AppError> {
+        let output = Command::new(&self.path)
+            .args(["-dryrun", "-user"])
+            .env("QUADLET_UNIT_DIRS", dir)
+            .output()
+            .map_err(|e| {
+                AppError::new(
+                    ErrorCode::GeneratorFailed,
+                    format!("Failed to execute {}: {}", self.path.display(), e),
+                )
+            })?;
+        Ok(DryRun {
+            units: parse_units(&String::from_utf8_lossy(&output.stdout)),
+            messages: String::from_utf8_lossy(&output.stderr)
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .map(String::from)
+                .collect(),
+        })
+    }
+
+    /// Generador de prueba: un script que crea un servicio por cada
+    /// `.container` con `Image=` y rechaza los que no lo tienen
+    #[cfg(test)]
+    pub fn stub(dir: &Path) -> Self {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = dir.join("quadlet");
+        std::fs::write(&path, STUB_GENERATOR).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        Self::new(path)
+    }
+}
+
+#[cfg(test)]
+const STUB_GENERATOR: &str = r#"#!/bin/sh
+[ "$1" = "-dryrun" ] || exit 2
+status=0
+for file in $(find "$QUADLET_UNIT_DIRS" -name '*.container' | sort); do
+    name=$(basename "$file" .container)
+    image=$(sed -n 's/^Image=//p' "$file")
+    if [ -n "$image" ]; then
+        echo "---$name.service---"
+        echo "[Service]"
+        echo "ExecStart=/usr/bin/podman run --name systemd-$name $image"
+        echo
+    else
+        echo "quadlet-generator[1]: converting \"$name.container\": no Image or Rootfs key specified" >&2
+        status=1
+    fi
+done
+exit $status
+"#;
+
+/// Separa la salida de `quadlet -dryrun` en unidades
+fn parse_units(output: &str) -> BTreeMap<String, String> {
+    let mut units = BTreeMap::new();
+    let mut current: Option<(String, String)> = None;
+    for line in output.lines() {
+        let header = line
+            .strip_prefix("---")
+            .and_then(|rest| rest.strip_suffix("---"))
+            .filter(|name| !name.is_empty() && !name.contains(char::is_whitespace));
+        if let Some(name) = header {
+            if let Some((name, content)) = current.take() {
+                units.insert(name, content);
+            }
+            current = Some((name.to_string(), String::new()));
+        } else if let Some((_, content)) = &mut current {
+            content.push_str(line);
+            content.push('\n');
+        }
+    }
+    if let Some((name, content)) = current {
+        units.insert(name, content);
+    }
+    units
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_units() {
+        let output = "---web.service---\n[Unit]\nSourcePath=/q/web.container\n\n\
+                      [Service]\nExecStart=podman run nginx\n\
+                      ---data-volume.service---\n[Service]\nType=oneshot\n";
+        let units = parse_units(output);
+        assert_eq!(units.len(), 2);
+        assert_eq!(
+            units["web.service"],
+            "[Unit]\nSourcePath=/q/web.container\n\n[Service]\nExecStart=podman run nginx\n"
+        );
+        assert_eq!(units["data-volume.service"], "[Service]\nType=oneshot\n");
+        assert!(parse_units("").is_empty());
+    }
+
+    #[test]
+    fn test_dry_run_with_stub() {
+        let bin = tempfile::tempdir().unwrap();
+        let quadlets = tempfile::tempdir().unwrap();
+        std::fs::create_dir(quadlets.path().join("apps")).unwrap();
+        std::fs::write(
+            quadlets.path().join("apps/web.container"),
+            "[Container]\nImage=nginx\n",
+        )
+        .unwrap();
+        std::fs::write(quadlets.path().join("db.container"), "[Container]\n").unwrap();
+
+        let dry_run = Generator::stub(bin.path())
+            .dry_run(quadlets.path())
+            .unwrap();
+        assert_eq!(dry_run.units.keys().collect::<Vec<_>>(), ["web.service"]);
+        assert!(dry_run.units["web.service"].contains("podman run --name systemd-web nginx"));
+        assert_eq!(dry_run.messages.len(), 1);
+        assert!(dry_run.messages[0].contains("\"db.container\": no Image"));
+    }
+
+    #[test]
+    fn test_dry_run_missing_generator() {
+        let dir = tempfile::tempdir().unwrap();
+        let error = Generator::new(dir.path().join("missing"))
+            .dry_run(dir.path())
+            .unwrap_err();
+        assert_eq!(error.code, ErrorCode::GeneratorFailed);
+    }
+}
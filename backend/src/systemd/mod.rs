@@ -0,0 +1,124 @@
+#[cfg(test)]
+mod fake;
+mod generator;
+mod systemctl;
+
+#[cfg(test)]
+pub use fake::FakeSystemd;
+pub use generator::{DEFAULT_GENERATOR, Generator};
+pub use systemctl::Systemctl;
+
+use std::{pin::Pin, sync::Arc};
+use tokio_stream::Stream;
+
+use crate::models::{AppError, ErrorCode, JournalQuery, UnitState};
+
+/// Líneas del journal según van llegando
+pub type LogStream = Pin<Box<dyn Stream<Item = String> + Send>>;
+
+/// Acceso a systemd.
+///
+/// Los handlers solo hablan con systemd a través de este trait, que se
+/// guarda en `AppState`; así se puede cambiar el mecanismo (p. ej. D-Bus) y
+/// probar el flujo completo sin un systemd real.
+pub trait SystemdManager: Send + Sync {
+    /// Vuelve a leer las unidades, lo que ejecuta el generador de Quadlet
+    fn daemon_reload(&self) -> Result<(), AppError>;
+
+    fn start(&self, unit: &str) -> Result<(), AppError>;
+
+    fn stop(&self, unit: &str) -> Result<(), AppError>;
+
+    fn restart(&self, unit: &str) -> Result<(), AppError>;
+
+    /// Pide a la unidad que vuelva a leer su configuración (`ExecReload=`)
+    fn reload(&self, unit: &str) -> Result<(), AppError>;
+
+    /// Deshabilita la unidad para que no arranque con la sesión
+    fn disable(&self, unit: &str) -> Result<(), AppError>;
+
+    /// Estado de la unidad, incluido si está habilitada
+    fn status(&self, unit: &str) -> Result<UnitState, AppError>;
+
+    /// Líneas del journal de la unidad que cumplen los filtros, de la más
+    /// antigua a la más reciente
+    fn logs(&self, unit: &str, query: &JournalQuery) -> Result<Vec<String>, AppError>;
+
+    /// Como [`SystemdManager::logs`], pero el flujo sigue abierto con las
+    /// líneas nuevas hasta que se descarta
+    fn follow(&self, unit: &str, query: &JournalQuery) -> Result<LogStream, AppError>;
+}
+
+/// Acceso a un [`SystemdManager`] desde los handlers.
+///
+/// Las órdenes de systemd (`systemctl`, `journalctl`) bloquean hasta que
+/// terminan, así que cada llamada se hace con `spawn_blocking` para no
+/// ocupar los hilos de Tokio.
+#[derive(Clone)]
+pub struct Systemd(Arc<dyn SystemdManager>);
+
+impl Systemd {
+    pub fn new(manager: Arc<dyn SystemdManager>) -> Self {
+        Self(manager)
+    }
+
+    async fn run<T, F>(&self, f: F) -> Result<T, AppError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&dyn SystemdManager) -> Result<T, AppError> + Send + 'static,
+    {
+        let manager = self.0.clone();
+        tokio::task::spawn_blocking(move || f(manager.as_ref()))
+            .await
+            .map_err(|e| {
+                AppError::new(
+                    ErrorCode::InternalError,
+                    format!("Systemd task failed: {}", e),
+                )
+            })?
+    }
+
+    pub async fn daemon_reload(&self) -> Result<(), AppError> {
+        self.run(|m| m.daemon_reload()).await
+    }
+
+    pub async fn start(&self, unit: &str) -> Result<(), AppError> {
+        let unit = unit.to_string();
+        self.run(move |m| m.start(&unit)).await
+    }
+
+    pub async fn stop(&self, unit: &str) -> Result<(), AppError> {
+        let unit = unit.to_string();
+        self.run(move |m| m.stop(&unit)).await
+    }
+
+    pub async fn restart(&self, unit: &str) -> Result<(), AppError> {
+        let unit = unit.to_string();
+        self.run(move |m| m.restart(&unit)).await
+    }
+
+    pub async fn reload(&self, unit: &str) -> Result<(), AppError> {
+        let unit = unit.to_string();
+        self.run(move |m| m.reload(&unit)).await
+    }
+
+    pub async fn disable(&self, unit: &str) -> Result<(), AppError> {
+        let unit = unit.to_string();
+        self.run(move |m| m.disable(&unit)).await
+    }
+
+    pub async fn status(&self, unit: &str) -> Result<UnitState, AppError> {
+        let unit = unit.to_string();
+        self.run(move |m| m.status(&unit)).await
+    }
+
+    pub async fn logs(&self, unit: &str, query: &JournalQuery) -> Result<Vec<String>, AppError> {
+        let (unit, query) = (unit.to_string(), query.clone());
+        self.run(move |m| m.logs(&unit, &query)).await
+    }
+
+    pub async fn follow(&self, unit: &str, query: &JournalQuery) -> Result<LogStream, AppError> {
+        let (unit, query) = (unit.to_string(), query.clone());
+        self.run(move |m| m.follow(&unit, &query)).await
+    }
+}
@@ -0,0 +1,180 @@
+use std::process::{Command, Stdio};
+use tokio::{
+    io::{AsyncBufRead, AsyncBufReadExt, BufReader},
+    sync::mpsc,
+};
+use tokio_stream::wrappers::ReceiverStream;
+use tracing::warn;
+
+use super::{LogStream, SystemdManager};
+use crate::models::{AppError, ErrorCode, JournalQuery, UNIT_STATE_PROPERTIES, UnitState};
+
+/// Implementación que ejecuta `systemctl --user` y `journalctl --user`
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Systemctl;
+
+impl SystemdManager for Systemctl {
+    fn daemon_reload(&self) -> Result<(), AppError> {
+        run("systemctl", &["daemon-reload"])
+            .map(|_| ())
+            .map_err(|e| {
+                AppError::new(
+                    ErrorCode::SystemdReloadFailed,
+                    format!("Failed to reload systemd: {}", e),
+                )
+            })
+    }
+
+    fn start(&self, unit: &str) -> Result<(), AppError> {
+        run("systemctl", &["start", "--", unit]).map(|_| ())
+    }
+
+    fn stop(&self, unit: &str) -> Result<(), AppError> {
+        run("systemctl", &["stop", "--", unit]).map(|_| ())
+    }
+
+    fn restart(&self, unit: &str) -> Result<(), AppError> {
+        run("systemctl", &["restart", "--", unit]).map(|_| ())
+    }
+
+    fn reload(&self, unit: &str) -> Result<(), AppError> {
+        run("systemctl", &["reload", "--", unit]).map(|_| ())
+    }
+
+    fn disable(&self, unit: &str) -> Result<(), AppError> {
+        run("systemctl", &["disable", "--", unit]).map(|_| ())
+    }
+
+    fn status(&self, unit: &str) -> Result<UnitState, AppError> {
+        let property = format!("--property={}", UNIT_STATE_PROPERTIES);
+        run("systemctl", &["show", &property, "--", unit])
+            .map(|output| UnitState::from_show(unit, &output))
+    }
+
+    fn logs(&self, unit: &str, query: &JournalQuery) -> Result<Vec<String>, AppError> {
+        let args = journal_args(unit, query);
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        run("journalctl", &args).map(|output| output.lines().map(String::from).collect())
+    }
+
+    fn follow(&self, unit: &str, query: &JournalQuery) -> Result<LogStream, AppError> {
+        let mut child = tokio::process::Command::new("journalctl")
+            .arg("--user")
+            .args(journal_args(unit, query))
+            .arg("--follow")
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| {
+                AppError::new(
+                    ErrorCode::SystemdCommandFailed,
+                    format!("Failed to execute journalctl: {}", e),
+                )
+            })?;
+        let stdout = child.stdout.take().ok_or_else(|| {
+            AppError::new(
+                ErrorCode::SystemdCommandFailed,
+                "Failed to read journalctl output",
+            )
+        })?;
+
+        // La tarea es dueña del proceso: cuando termina `kill_on_drop` para
+        // `journalctl`
+        let (tx, rx) = mpsc::channel(64);
+        let unit = unit.to_string();
+        tokio::spawn(async move {
+            let _child = child;
+            forward_lines(BufReader::new(stdout), tx, &unit).await;
+        });
+        Ok(Box::pin(ReceiverStream::new(rx)))
+    }
+}
+
+/// Envía por `tx` las líneas de `reader` hasta que se acaban o el cliente se
+/// desconecta.
+///
+/// Se espera también al cierre del canal y no solo a que falle un envío,
+/// porque una unidad sin actividad puede no volver a escribir nunca.
+async fn forward_lines<R: AsyncBufRead + Unpin>(reader: R, tx: mpsc::Sender<String>, unit: &str) {
+    let mut lines = reader.lines();
+    loop {
+        let line = tokio::select! {
+            _ = tx.closed() => break,
+            line = lines.next_line() => line,
+        };
+        match line {
+            Ok(Some(line)) => {
+                if tx.send(line).await.is_err() {
+                    break;
+                }
+            }
+            Ok(None) => break,
+            Err(e) => {
+                warn!("Failed to read journal of {}: {}", unit, e);
+                break;
+            }
+        }
+    }
+}
+
+/// Argumentos de `journalctl` (sin `--user`) para leer el journal de la unidad
+fn journal_args(unit: &str, query: &JournalQuery) -> Vec<String> {
+    let mut args = vec![format!("--unit={}", unit)];
+    args.extend(query.args());
+    args.push("--no-pager".to_string());
+    args.push("--output=short-iso".to_string());
+    args
+}
+
+/// Ejecuta `program --user` con los argumentos y devuelve la salida estándar
+fn run(program: &str, args: &[&str]) -> Result<String, AppError> {
+    let output = Command::new(program)
+        .arg("--user")
+        .args(args)
+        .output()
+        .map_err(|e| {
+            AppError::new(
+                ErrorCode::SystemdCommandFailed,
+                format!("Failed to execute {}: {}", program, e),
+            )
+        })?;
+
+    if !output.status.success() {
+        return Err(AppError::new(
+            ErrorCode::SystemdCommandFailed,
+            format!(
+                "{} {} failed: {}",
+                program,
+                args.join(" "),
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+        ));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tokio::io::AsyncWriteExt;
+
+    #[tokio::test]
+    async fn test_forward_lines_stops_when_client_disconnects() {
+        let (reader, mut writer) = tokio::io::duplex(64);
+        let (tx, mut rx) = mpsc::channel(4);
+        let task = tokio::spawn(forward_lines(BufReader::new(reader), tx, "web.service"));
+
+        writer.write_all(b"first\n").await.unwrap();
+        assert_eq!(rx.recv().await.as_deref(), Some("first"));
+
+        // Sin más salida la tarea solo puede terminar por la desconexión
+        drop(rx);
+        tokio::time::timeout(Duration::from_secs(5), task)
+            .await
+            .expect("forward_lines kept waiting for output")
+            .unwrap();
+    }
+}